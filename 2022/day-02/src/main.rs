@@ -1,17 +1,47 @@
+use std::ops::RangeInclusive;
+
 const INPUT_TXT: &str = include_str!("input.txt");
 
 fn main() {
     let input = read_input(INPUT_TXT);
     println!("Answer to first parts is {}", solve_first(&input));
     println!("Answer to second parts is {}", solve_second(&input));
+    // --uniform makes the random opponent ignore the guide, --score N prints the chance
+    // to get exactly N and at least N points
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let option = |name: &str| args.iter().position(|arg| arg == name).map(|i| &args[i + 1..]);
+    let opponent = if option("--uniform").is_some() {
+        OpponentDistribution::uniform()
+    } else {
+        OpponentDistribution::from_guide(&input)
+    };
+    let score = match option("--score").map(|rest| rest.first().map(|x| x.parse::<usize>())) {
+        Some(Some(Ok(score))) => Some(score),
+        Some(_) => {
+            println!("Usage: --score N");
+            return;
+        },
+        None => None,
+    };
+    for strategy in [Strategy::Hand, Strategy::Outcome] {
+        let dist = simulate(&input, strategy, &opponent);
+        println!("Expected score against a random opponent is {:.2}, most likely {}", dist.expected(), dist.most_likely());
+        if let Some(score) = score {
+            println!("Chance to score {} is {:.4}, at least {} is {:.4}",
+                score, dist.probability(score), score, dist.probability_at_least(score));
+        }
+    }
+    let opponents = read_opponents(&input);
+    let (score, _) = best_moves(&opponents, 0..=opponents.len()).unwrap();
+    println!("Best possible score is {}", score);
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 enum Hand {
     Rock = 1, Paper = 2, Scissors = 3
 }
 
-#[derive(Copy, Clone, Eq, PartialEq)]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
 enum GameResult {
     Lose = 0, Draw = 3, Win = 6
 }
@@ -109,6 +139,164 @@ fn solve_second(input: &Vec<&str>) -> i32 {
     return score;
 }
 
+// the way the second column of the strategy guide is read
+#[derive(Copy, Clone, Eq, PartialEq)]
+enum Strategy {
+    // the column is the hand to play (first part)
+    Hand,
+    // the column is the result to get (second part)
+    Outcome,
+}
+
+// score of a single round for the given guide column when opponent plays `opponent`
+fn round_score(strategy: Strategy, column: char, opponent: Hand) -> i32 {
+    match strategy {
+        Strategy::Hand => {
+            let player = Hand::new(column);
+            (player.play_against(opponent) as i32) + (player as i32)
+        }
+        Strategy::Outcome => {
+            let res = GameResult::new(column);
+            (res as i32) + (pick_hand(opponent, res) as i32)
+        }
+    }
+}
+
+const HANDS: [Hand; 3] = [Hand::Rock, Hand::Paper, Hand::Scissors];
+const RESULTS: [GameResult; 3] = [GameResult::Lose, GameResult::Draw, GameResult::Win];
+// highest score a single round can give: win with scissors
+const MAX_ROUND_SCORE: usize = 9;
+
+// probabilities of the opponent playing rock, paper and scissors in a round
+#[derive(Copy, Clone)]
+struct OpponentDistribution([f64; 3]);
+
+impl OpponentDistribution {
+    // weights don't have to sum up to 1, they are normalized
+    fn new(rock: f64, paper: f64, scissors: f64) -> OpponentDistribution {
+        let total = rock + paper + scissors;
+        assert!(rock >= 0.0 && paper >= 0.0 && scissors >= 0.0 && total > 0.0);
+        OpponentDistribution([rock / total, paper / total, scissors / total])
+    }
+
+    fn uniform() -> OpponentDistribution {
+        OpponentDistribution::new(1.0, 1.0, 1.0)
+    }
+
+    // frequencies of the hands in the first column of the guide
+    fn from_guide(input: &Vec<&str>) -> OpponentDistribution {
+        let mut counts = [0.0; 3];
+        for hand in read_opponents(input) {
+            counts[hand as usize - 1] += 1.0;
+        }
+        OpponentDistribution::new(counts[0], counts[1], counts[2])
+    }
+
+    fn probability(&self, hand: Hand) -> f64 {
+        self.0[hand as usize - 1]
+    }
+}
+
+// probability of every total score, where the index is the score
+struct ScoreDistribution(Vec<f64>);
+
+impl ScoreDistribution {
+    fn probability(&self, score: usize) -> f64 {
+        self.0.get(score).copied().unwrap_or(0.0)
+    }
+
+    fn probability_at_least(&self, score: usize) -> f64 {
+        self.0.iter().skip(score).sum()
+    }
+
+    fn expected(&self) -> f64 {
+        self.0.iter().enumerate().map(|(score, p)| score as f64 * p).sum()
+    }
+
+    // the score with the highest probability, the lowest one wins a tie
+    fn most_likely(&self) -> usize {
+        let mut best = 0;
+        for (score, &p) in self.0.iter().enumerate() {
+            if p > self.0[best] {
+                best = score;
+            }
+        }
+        return best;
+    }
+}
+
+// plays the guide against the opponent picking the hands at random with the given distribution
+// (ignoring the first column) and returns the exact distribution of the total score
+fn simulate(input: &Vec<&str>, strategy: Strategy, opponent: &OpponentDistribution) -> ScoreDistribution {
+    let mut dist = vec![1.0];
+    for line in input.iter() {
+        let column = line.chars().nth(2).unwrap();
+        let mut next = vec![0.0; dist.len() + MAX_ROUND_SCORE];
+        for hand in HANDS {
+            let p = opponent.probability(hand);
+            if p == 0.0 {
+                continue;
+            }
+            let score = round_score(strategy, column, hand) as usize;
+            for (total, &q) in dist.iter().enumerate() {
+                next[total + score] += p * q;
+            }
+        }
+        dist = next;
+    }
+    // drop the unreachable tail
+    while dist.len() > 1 && *dist.last().unwrap() == 0.0 {
+        dist.pop();
+    }
+    ScoreDistribution(dist)
+}
+
+fn read_opponents(input: &Vec<&str>) -> Vec<Hand> {
+    input.iter().map(|line| Hand::new(line.chars().nth(0).unwrap())).collect()
+}
+
+// searches for the hands to play against the known opponent's hands which give the
+// highest total score while the number of won rounds stays within `wins`;
+// returns None if no sequence satisfies the constraint
+fn best_moves(opponents: &[Hand], wins: RangeInclusive<usize>) -> Option<(i32, Vec<Hand>)> {
+    // best[w] is the highest score after the played rounds with exactly w wins
+    let mut best: Vec<Option<i32>> = vec![Some(0)];
+    // choices[round][w] is the hand which lead to best[w] after the round
+    let mut choices: Vec<Vec<Hand>> = Vec::with_capacity(opponents.len());
+    for &opponent in opponents.iter() {
+        let mut next: Vec<Option<i32>> = vec![None; best.len() + 1];
+        let mut picked = vec![Hand::Rock; best.len() + 1];
+        for (w, score) in best.iter().enumerate() {
+            let Some(score) = score else { continue };
+            for res in RESULTS {
+                let player = pick_hand(opponent, res);
+                let w_next = if res == GameResult::Win { w + 1 } else { w };
+                let candidate = score + (res as i32) + (player as i32);
+                if next[w_next].is_none_or(|s| candidate > s) {
+                    next[w_next] = Some(candidate);
+                    picked[w_next] = player;
+                }
+            }
+        }
+        best = next;
+        choices.push(picked);
+    }
+    let (mut w, score) = best.iter().enumerate()
+        .filter(|(w, _)| wins.contains(w))
+        .filter_map(|(w, score)| score.map(|s| (w, s)))
+        .max_by_key(|&(_, s)| s)?;
+    // walk back through the choices to restore the hands
+    let mut moves = vec![Hand::Rock; opponents.len()];
+    for round in (0..opponents.len()).rev() {
+        let player = choices[round][w];
+        if player.play_against(opponents[round]) == GameResult::Win {
+            w -= 1;
+        }
+        moves[round] = player;
+    }
+    Some((score, moves))
+}
+
 fn read_input(file_content: &str) -> Vec<&str> {
     file_content.lines().into_iter().collect()
 }
@@ -136,5 +324,41 @@ mod tests {
         let input = read_input(TEST_INPUT_TXT);
         assert_eq!(solve_second(&input), 12);
     }
+
+    #[test]
+    fn simulation() {
+        let input = read_input(TEST_INPUT_TXT);
+        // opponent always plays rock: win with paper, draw with rock and lose with scissors
+        let dist = simulate(&input, Strategy::Hand, &OpponentDistribution::new(1.0, 0.0, 0.0));
+        assert_eq!(dist.probability(8 + 4 + 3), 1.0);
+        assert_eq!(dist.most_likely(), 15);
+        // hand brings 2 points on average on top of draw, lose and win
+        let dist = simulate(&input, Strategy::Outcome, &OpponentDistribution::uniform());
+        assert!((dist.expected() - 15.0).abs() < 1e-9);
+        assert_eq!(dist.probability(0), 0.0);
+        // the guide has every hand once, so it's the same as uniform
+        let dist = simulate(&input, Strategy::Hand, &OpponentDistribution::from_guide(&input));
+        assert!((dist.probability_at_least(0) - 1.0).abs() < 1e-9);
+        assert!((dist.expected() - 15.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn optimal_strategy() {
+        let input = read_input(TEST_INPUT_TXT);
+        let opponents = read_opponents(&input);
+        // winning every round
+        let (score, moves) = best_moves(&opponents, 0..=opponents.len()).unwrap();
+        assert_eq!(score, 8 + 9 + 7);
+        assert_eq!(moves, vec![Hand::Paper, Hand::Scissors, Hand::Rock]);
+        // at most one win: win with scissors, the rest are draws with rock and scissors
+        let (score, moves) = best_moves(&opponents, 0..=1).unwrap();
+        assert_eq!(score, 9 + 4 + 6);
+        assert_eq!(moves, vec![Hand::Rock, Hand::Scissors, Hand::Scissors]);
+        // no wins at all
+        let (score, _) = best_moves(&opponents, 0..=0).unwrap();
+        assert_eq!(score, 4 + 5 + 6);
+        // can't win more rounds than there are
+        assert!(best_moves(&opponents, 4..=5).is_none());
+    }
 }
 
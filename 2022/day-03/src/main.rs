@@ -1,81 +1,398 @@
+use std::ops::RangeInclusive;
+
 const INPUT_TXT: &str = include_str!("input.txt");
 
 fn main() {
     let input = read_input(INPUT_TXT);
     println!("Answer to first parts is {}", solve_first(&input));
     println!("Answer to second parts is {}", solve_second(&input));
+    // --range FIRST LAST or --bytes replace the puzzle's priorities,
+    // --layout COMPARTMENTS GROUP_SIZE replaces the puzzle's layout,
+    // --explain prints shared items of every rucksack and group
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let option = |name: &str| args.iter().position(|arg| arg == name).map(|i| &args[i + 1..]);
+    let explain = option("--explain").is_some();
+    let layout = match option("--layout").map(|rest| rest.iter().take(2).map(|x| x.parse::<usize>()).collect::<Vec<_>>()) {
+        Some(numbers) => match numbers[..] {
+            [Ok(compartments), Ok(group_size)] => match Layout::new(compartments, group_size) {
+                Ok(layout) => layout,
+                Err(e) => {
+                    println!("{}", e);
+                    return;
+                },
+            },
+            _ => {
+                println!("Usage: --layout COMPARTMENTS GROUP_SIZE");
+                return;
+            },
+        },
+        None => Layout::default(),
+    };
+    let custom_layout = option("--layout").is_some();
+    if let Some(rest) = option("--range") {
+        let [first, last, ..] = rest else {
+            println!("Usage: --range FIRST LAST");
+            return;
+        };
+        let bound = |x: &String| x.chars().next();
+        let (Some(first), Some(last)) = (bound(first), bound(last)) else {
+            println!("Usage: --range FIRST LAST");
+            return;
+        };
+        match CharRange::new(first, last) {
+            Ok(scheme) => report(&input, &scheme, &layout, explain),
+            Err(e) => println!("{}", e),
+        }
+    } else if option("--bytes").is_some() {
+        report(&input, &Bytes, &layout, explain);
+    } else if explain || custom_layout {
+        report(&input, &Letters, &layout, explain);
+    }
 }
 
-// gets index for the given char, where 'a' has index of 1 and 'A' has index of 27, etc.
-fn get_index(c: char) -> i32 {
-    if 'a' <= c && c <= 'z' {
-        return c as i32 - 'a' as i32 + 1;
-    } else if 'A' <= c && c <= 'z' {
-        return c as i32 - 'A' as i32 + 27;
+// answers for the given rules, wide schemes such as Unicode blocks need larger bitsets
+fn report(input: &Vec<&str>, scheme: &impl Priority, layout: &Layout, explain_all: bool) {
+    if scheme.size() <= BitSet::<5>::CAPACITY {
+        report_with::<5>(input, scheme, layout, explain_all);
+    } else {
+        report_with::<1024>(input, scheme, layout, explain_all);
     }
-    panic!("unexpected char in get_index")
 }
 
-// gets index of the set bit, assuming only one bit is set, otherwise will return the highest set bit in mask
-fn get_setbit_index(mut mask: u64) -> i32 {
-    let mut counter = 0;
-    while mask != 1 {
-        mask >>= 1;
-        counter += 1;
+fn report_with<const N: usize>(input: &Vec<&str>, scheme: &impl Priority, layout: &Layout, explain_all: bool) {
+    match solve_first_with::<N>(input, scheme, layout) {
+        Ok(sum) => println!("Sum of priorities in rucksacks is {}", sum),
+        Err(e) => println!("Rucksacks can't be checked: {}", e),
+    }
+    match solve_second_with::<N>(input, scheme, layout) {
+        Ok(sum) => println!("Sum of priorities of badges is {}", sum),
+        Err(e) => println!("Groups can't be checked: {}", e),
+    }
+    if explain_all {
+        if let Ok(analysis) = analyze_rucksacks::<N>(input, scheme, layout) {
+            print!("{}", explain(&analysis));
+        }
+        if let Ok(analysis) = analyze_groups::<N>(input, scheme, layout) {
+            print!("{}", explain(&analysis));
+        }
     }
-    return counter;
 }
 
-fn solve_first(input: &Vec<&str>) -> i32 {
-    let mut sum = 0;
-    let mut seen = 0u64;
-    for line in input {
-        assert!(line.len() % 2 == 0);
-        // set the bits for the first part
-        line[..line.len()/2].chars().for_each(|c| set_bit(&mut seen, get_index(c)));
-        // search for duplicate
-        for c in line[line.len()/2..].chars() {
-            let idx = get_index(c);
-            if is_bit_set(seen, idx) {
-                sum += idx;
-                break;
-            }
+// maps items to their priorities, which start from 1 and stay below `size()`
+trait Priority {
+    fn priority(&self, c: char) -> Option<usize>;
+    // all priorities of the scheme are below this value
+    fn size(&self) -> usize;
+}
+
+// the puzzle's scheme: 'a' has priority of 1 and 'A' has priority of 27, etc.
+struct Letters;
+
+impl Priority for Letters {
+    fn priority(&self, c: char) -> Option<usize> {
+        match c {
+            'a'..='z' => Some(c as usize - 'a' as usize + 1),
+            'A'..='Z' => Some(c as usize - 'A' as usize + 27),
+            _ => None,
         }
-        seen = 0;
     }
-    return sum;
+
+    fn size(&self) -> usize {
+        53
+    }
 }
 
-fn solve_second(input: &Vec<&str>) -> i32 {
-    let mut sum = 0;
-    // 3 bitmaps to store the 'seen' chars, where 0b10 == 'a'
-    let mut seen = [0u64; 3];
-    for (i, line) in input.iter().enumerate() {
-        let idx = i % 3;
-        // set bits for this line
-        line.chars().for_each(|c| set_bit(&mut seen[idx], get_index(c)));
-        if idx == 2 {
-            // mask holds common item across three strings
-            let mask = seen[0] & seen[1] & seen[2];
-            sum += get_setbit_index(mask);
-            seen = [0, 0, 0];
+// every char in the inclusive range, where the first one has priority of 1,
+// e.g. '0'..='9' for digits or a Unicode block
+struct CharRange(char, char);
+
+impl CharRange {
+    fn new(first: char, last: char) -> Result<CharRange, Error> {
+        if first > last {
+            return Err(Error::InvalidRange { first, last });
         }
+        Ok(CharRange(first, last))
     }
-    return sum;
 }
 
-fn read_input(file_content: &str) -> Vec<&str> {
-    file_content.lines().into_iter().collect()
+impl Priority for CharRange {
+    fn priority(&self, c: char) -> Option<usize> {
+        if self.0 <= c && c <= self.1 {
+            Some(c as usize - self.0 as usize + 1)
+        } else {
+            None
+        }
+    }
+
+    fn size(&self) -> usize {
+        self.1 as usize - self.0 as usize + 2
+    }
+}
+
+// any byte value, where priority is the value plus 1
+struct Bytes;
+
+impl Priority for Bytes {
+    fn priority(&self, c: char) -> Option<usize> {
+        if (c as u32) < 256 { Some(c as usize + 1) } else { None }
+    }
+
+    fn size(&self) -> usize {
+        257
+    }
+}
+
+// fixed-size bitset of N 64-bit words
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct BitSet<const N: usize>([u64; N]);
+
+impl<const N: usize> BitSet<N> {
+    const CAPACITY: usize = N * 64;
+
+    fn new() -> BitSet<N> {
+        BitSet([0; N])
+    }
+
+    fn set(&mut self, n: usize) {
+        self.0[n / 64] |= 1 << (n % 64);
+    }
+
+    // the lowest set bit
+    fn first(&self) -> Option<usize> {
+        self.0.iter().enumerate()
+            .find(|(_, &w)| w != 0)
+            .map(|(i, w)| i * 64 + w.trailing_zeros() as usize)
+    }
+
+    // all set bits in increasing order
+    fn iter(&self) -> impl Iterator<Item = usize> + '_ {
+        self.0.iter().enumerate().flat_map(|(i, &w)| {
+            let mut word = w;
+            std::iter::from_fn(move || {
+                if word == 0 {
+                    return None;
+                }
+                let bit = word.trailing_zeros() as usize;
+                // clear the lowest set bit
+                word &= word - 1;
+                Some(i * 64 + bit)
+            })
+        })
+    }
+}
+
+impl<const N: usize> std::ops::BitAnd for BitSet<N> {
+    type Output = BitSet<N>;
+
+    fn bitand(self, rhs: BitSet<N>) -> BitSet<N> {
+        let mut res = self;
+        res.0.iter_mut().zip(rhs.0.iter()).for_each(|(a, b)| *a &= b);
+        res
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Error {
+    // layout parameters must be positive
+    InvalidLayout { compartments: usize, group_size: usize },
+    // number of lines isn't a multiple of the group size
+    IncompleteGroup { lines: usize, group_size: usize },
+    // items of the line can't be split into equally sized compartments
+    UnevenCompartments { line: usize, items: usize, compartments: usize },
+    // item which isn't covered by the priority scheme, lines start from 1
+    UnknownItem { line: usize, item: char },
+    // nothing is shared between the compartments or the rucksacks of the group
+    NoCommonItem { line: usize },
+    // the first char of the range comes after the last one
+    InvalidRange { first: char, last: char },
+    // priorities of the scheme don't fit into the bitset
+    SchemeTooLarge { size: usize, capacity: usize },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::InvalidLayout { compartments, group_size } =>
+                write!(f, "invalid layout: {} compartments, groups of {}", compartments, group_size),
+            Error::IncompleteGroup { lines, group_size } =>
+                write!(f, "{} lines can't be split into groups of {}", lines, group_size),
+            Error::UnevenCompartments { line, items, compartments } =>
+                write!(f, "line {}: {} items can't be split into {} compartments", line, items, compartments),
+            Error::UnknownItem { line, item } => write!(f, "line {}: unexpected item {:?}", line, item),
+            Error::NoCommonItem { line } => write!(f, "line {}: no common item", line),
+            Error::InvalidRange { first, last } => write!(f, "invalid range: {:?} comes after {:?}", first, last),
+            Error::SchemeTooLarge { size, capacity } =>
+                write!(f, "{} priorities don't fit into a set of {}", size - 1, capacity - 1),
+        }
+    }
 }
 
-#[inline(always)]
-fn set_bit(mask: &mut u64, n: i32) {
-    *mask |= 1 << n;
+// how the rucksacks are organized: number of compartments in each of them
+// and number of elves in a group
+#[derive(Clone, Copy, Debug)]
+struct Layout {
+    compartments: usize,
+    group_size: usize,
 }
 
-#[inline(always)]
-fn is_bit_set(mask: u64, n: i32) -> bool {
-    (mask & (1 << n)) != 0
+impl Layout {
+    fn new(compartments: usize, group_size: usize) -> Result<Layout, Error> {
+        if compartments == 0 || group_size == 0 {
+            return Err(Error::InvalidLayout { compartments, group_size });
+        }
+        Ok(Layout { compartments, group_size })
+    }
+}
+
+impl Default for Layout {
+    // the puzzle's layout
+    fn default() -> Layout {
+        Layout { compartments: 2, group_size: 3 }
+    }
+}
+
+// collects priorities of all items in the string, `line` is used for error reporting
+fn make_set<const N: usize>(items: &str, line: usize, scheme: &impl Priority) -> Result<BitSet<N>, Error> {
+    if scheme.size() > BitSet::<N>::CAPACITY {
+        return Err(Error::SchemeTooLarge { size: scheme.size(), capacity: BitSet::<N>::CAPACITY });
+    }
+    let mut set = BitSet::new();
+    for item in items.chars() {
+        let priority = scheme.priority(item).ok_or(Error::UnknownItem { line, item })?;
+        set.set(priority);
+    }
+    return Ok(set);
+}
+
+// splits items into equally sized compartments
+fn split_compartments(items: &str, line: usize, compartments: usize) -> Result<Vec<&str>, Error> {
+    let count = items.chars().count();
+    if !count.is_multiple_of(compartments) {
+        return Err(Error::UnevenCompartments { line, items: count, compartments });
+    }
+    let size = count / compartments;
+    // byte offsets of the compartment bounds, items may take more than one byte
+    let mut bounds: Vec<usize> = items.char_indices().map(|(i, _)| i).step_by(size.max(1)).collect();
+    bounds.truncate(compartments);
+    bounds.push(items.len());
+    Ok(bounds.windows(2).map(|w| &items[w[0]..w[1]]).collect())
+}
+
+// item found in every compartment of a rucksack or in every rucksack of a group
+#[derive(Debug, PartialEq, Eq)]
+struct SharedItem {
+    item: char,
+    priority: usize,
+    // positions of the item (in chars from the start of the line) in every compartment or rucksack
+    positions: Vec<Vec<usize>>,
+}
+
+// shared items of a single rucksack or a group, sorted by priority
+#[derive(Debug, PartialEq, Eq)]
+struct Analysis {
+    lines: RangeInclusive<usize>,
+    shared: Vec<SharedItem>,
+}
+
+// part of a line being compared with the others: (line, offset of the first item in chars, items)
+type Part<'a> = (usize, usize, &'a str);
+
+// finds items common to all parts, `first_line` is used for error reporting
+fn find_shared<const N: usize>(parts: &[Part], first_line: usize, scheme: &impl Priority) -> Result<Vec<SharedItem>, Error> {
+    let mut common: Option<BitSet<N>> = None;
+    for &(line, _, items) in parts {
+        let set = make_set::<N>(items, line, scheme)?;
+        common = Some(common.map_or(set, |c| c & set));
+    }
+    let common = common.unwrap_or_else(BitSet::new);
+    if common.first().is_none() {
+        return Err(Error::NoCommonItem { line: first_line });
+    }
+    let shared = common.iter().map(|priority| {
+        // all items are known to the scheme at this point
+        let is_shared = |c: char| scheme.priority(c) == Some(priority);
+        let positions: Vec<Vec<usize>> = parts.iter().map(|&(_, offset, items)| {
+            items.chars().enumerate().filter(|&(_, c)| is_shared(c)).map(|(i, _)| offset + i).collect()
+        }).collect();
+        let item = parts[0].2.chars().find(|&c| is_shared(c)).unwrap();
+        SharedItem { item, priority, positions }
+    }).collect();
+    return Ok(shared);
+}
+
+// shared items between the compartments of every rucksack
+fn analyze_rucksacks<const N: usize>(input: &Vec<&str>, scheme: &impl Priority, layout: &Layout) -> Result<Vec<Analysis>, Error> {
+    let mut res = vec![];
+    for (i, items) in input.iter().enumerate() {
+        let line = i + 1;
+        let size = items.chars().count() / layout.compartments;
+        let parts: Vec<Part> = split_compartments(items, line, layout.compartments)?
+            .into_iter().enumerate()
+            .map(|(k, compartment)| (line, k * size, compartment))
+            .collect();
+        res.push(Analysis { lines: line..=line, shared: find_shared::<N>(&parts, line, scheme)? });
+    }
+    return Ok(res);
+}
+
+// shared items between the rucksacks of every group
+fn analyze_groups<const N: usize>(input: &Vec<&str>, scheme: &impl Priority, layout: &Layout) -> Result<Vec<Analysis>, Error> {
+    if !input.len().is_multiple_of(layout.group_size) {
+        return Err(Error::IncompleteGroup { lines: input.len(), group_size: layout.group_size });
+    }
+    let mut res = vec![];
+    for (g, group) in input.chunks(layout.group_size).enumerate() {
+        let first_line = g * layout.group_size + 1;
+        let parts: Vec<Part> = group.iter().enumerate()
+            .map(|(i, &items)| (first_line + i, 0, items))
+            .collect();
+        // errors point to the first line of the group
+        let shared = find_shared::<N>(&parts, first_line, scheme)?;
+        res.push(Analysis { lines: first_line..=first_line + group.len() - 1, shared });
+    }
+    return Ok(res);
+}
+
+// human readable details of the analysis, one line per rucksack or group
+fn explain(analysis: &[Analysis]) -> String {
+    let mut out = String::new();
+    for a in analysis {
+        if a.lines.start() == a.lines.end() {
+            out += &format!("line {}:", a.lines.start());
+        } else {
+            out += &format!("lines {}-{}:", a.lines.start(), a.lines.end());
+        }
+        for shared in &a.shared {
+            out += &format!(" {:?} (priority {}) at {:?}", shared.item, shared.priority, shared.positions);
+        }
+        out += "\n";
+    }
+    return out;
+}
+
+// sum of the lowest shared priorities
+fn sum_priorities(analysis: &[Analysis]) -> usize {
+    analysis.iter().map(|a| a.shared[0].priority).sum()
+}
+
+fn solve_first(input: &Vec<&str>) -> i32 {
+    solve_first_with::<1>(input, &Letters, &Layout::default()).unwrap() as i32
+}
+
+fn solve_first_with<const N: usize>(input: &Vec<&str>, scheme: &impl Priority, layout: &Layout) -> Result<usize, Error> {
+    Ok(sum_priorities(&analyze_rucksacks::<N>(input, scheme, layout)?))
+}
+
+fn solve_second(input: &Vec<&str>) -> i32 {
+    solve_second_with::<1>(input, &Letters, &Layout::default()).unwrap() as i32
+}
+
+fn solve_second_with<const N: usize>(input: &Vec<&str>, scheme: &impl Priority, layout: &Layout) -> Result<usize, Error> {
+    Ok(sum_priorities(&analyze_groups::<N>(input, scheme, layout)?))
+}
+
+fn read_input(file_content: &str) -> Vec<&str> {
+    file_content.lines().into_iter().collect()
 }
 
 
@@ -95,5 +412,108 @@ mod tests {
         let input = read_input(TEST_INPUT_TXT);
         assert_eq!(solve_second(&input), 70);
     }
+
+    #[test]
+    fn priorities() {
+        assert_eq!(Letters.priority('a'), Some(1));
+        assert_eq!(Letters.priority('Z'), Some(52));
+        // punctuation between 'Z' and 'a' isn't an item
+        assert_eq!(Letters.priority('['), None);
+        assert_eq!(Letters.priority('_'), None);
+        assert_eq!(CharRange('0', '9').priority('0'), Some(1));
+        assert_eq!(CharRange('0', '9').priority('a'), None);
+        assert_eq!(CharRange::new('z', 'a').err(), Some(Error::InvalidRange { first: 'z', last: 'a' }));
+        assert_eq!(Bytes.priority('\u{ff}'), Some(256));
+        assert_eq!(Bytes.priority('\u{100}'), None);
+    }
+
+    #[test]
+    fn bitset() {
+        let mut a = BitSet::<4>::new();
+        let mut b = BitSet::<4>::new();
+        for n in [1, 63, 64, 200, 255] {
+            a.set(n);
+        }
+        for n in [0, 63, 200, 254] {
+            b.set(n);
+        }
+        assert_eq!(a.iter().collect::<Vec<_>>(), vec![1, 63, 64, 200, 255]);
+        let common = a & b;
+        assert_eq!(common.iter().collect::<Vec<_>>(), vec![63, 200]);
+        assert_eq!(common.first(), Some(63));
+        assert_eq!(BitSet::<2>::new().first(), None);
+    }
+
+    #[test]
+    fn other_alphabets() {
+        let layout = Layout::default();
+        let input = vec!["1231", "4564", "7897"];
+        assert_eq!(solve_first_with::<1>(&input, &CharRange('0', '9'), &layout), Ok(2 + 5 + 8));
+        let input = vec!["\u{3b1}\u{3b2}\u{3b3}", "\u{3b4}\u{3b3}", "\u{3b3}\u{3b5}"];
+        assert_eq!(solve_second_with::<1>(&input, &CharRange('\u{3b1}', '\u{3c9}'), &layout), Ok(3));
+        let input = vec!["\u{ff}ab\u{ff}"];
+        assert_eq!(solve_first_with::<5>(&input, &Bytes, &layout), Ok(256));
+        // CJK ideographs need a wider set
+        let ideographs = CharRange::new('\u{4e00}', '\u{9fff}').unwrap();
+        let input = vec!["\u{4e00}\u{9fff}\u{4e01}\u{9fff}"];
+        assert_eq!(solve_first_with::<5>(&input, &ideographs, &layout), Err(Error::SchemeTooLarge { size: 20993, capacity: 320 }));
+        assert_eq!(solve_first_with::<1024>(&input, &ideographs, &layout), Ok(20992));
+    }
+
+    #[test]
+    fn layouts() {
+        assert_eq!(Layout::new(0, 3).unwrap_err(), Error::InvalidLayout { compartments: 0, group_size: 3 });
+        assert_eq!(Layout::new(2, 0).unwrap_err(), Error::InvalidLayout { compartments: 2, group_size: 0 });
+        // three compartments sharing 'c', groups of two sharing 'a' and 'd'
+        let layout = Layout::new(3, 2).unwrap();
+        let input = vec!["abcdacxyc", "dddaaaccc"];
+        assert_eq!(solve_first_with::<1>(&input, &Letters, &layout), Err(Error::NoCommonItem { line: 2 }));
+        let input = vec!["abcdacxyc", "cdcaacccc"];
+        assert_eq!(solve_first_with::<1>(&input, &Letters, &layout), Ok(3 + 3));
+        assert_eq!(solve_second_with::<1>(&input, &Letters, &layout), Ok(1));
+        // a single rucksack in a single compartment
+        let layout = Layout::new(1, 1).unwrap();
+        assert_eq!(solve_first_with::<1>(&vec!["ba"], &Letters, &layout), Ok(1));
+        assert_eq!(solve_second_with::<1>(&vec!["ba"], &Letters, &layout), Ok(1));
+    }
+
+    #[test]
+    fn analysis() {
+        let layout = Layout::default();
+        let input = read_input(TEST_INPUT_TXT);
+        let rucksacks = analyze_rucksacks::<1>(&input, &Letters, &layout).unwrap();
+        assert_eq!(rucksacks.len(), 6);
+        assert_eq!(rucksacks[0], Analysis {
+            lines: 1..=1,
+            shared: vec![SharedItem { item: 'p', priority: 16, positions: vec![vec![4], vec![23]] }],
+        });
+        let groups = analyze_groups::<1>(&input, &Letters, &layout).unwrap();
+        assert_eq!(groups[1].lines, 4..=6);
+        assert_eq!(groups[1].shared[0].item, 'Z');
+        assert_eq!(groups[1].shared[0].positions, vec![vec![6], vec![12, 14], vec![2, 8]]);
+        // more than one shared item
+        let input = vec!["aBcBad"];
+        let rucksacks = analyze_rucksacks::<1>(&input, &Letters, &layout).unwrap();
+        let items: Vec<char> = rucksacks[0].shared.iter().map(|s| s.item).collect();
+        assert_eq!(items, vec!['a', 'B']);
+        assert_eq!(explain(&rucksacks), "line 1: 'a' (priority 1) at [[0], [4]] 'B' (priority 28) at [[1], [3]]\n");
+        assert_eq!(explain(&groups[..1]), "lines 1-3: 'r' (priority 18) at [[2, 11], [16, 24, 26], [7]]\n");
+    }
+
+    #[test]
+    fn errors() {
+        let layout = Layout::default();
+        let input = read_input(TEST_INPUT_TXT);
+        assert_eq!(solve_second_with::<1>(&input[..5].to_vec(), &Letters, &layout),
+            Err(Error::IncompleteGroup { lines: 5, group_size: 3 }));
+        assert_eq!(solve_first_with::<1>(&vec!["abc"], &Letters, &layout),
+            Err(Error::UnevenCompartments { line: 1, items: 3, compartments: 2 }));
+        assert_eq!(solve_first_with::<1>(&vec!["aa", "a1"], &Letters, &layout),
+            Err(Error::UnknownItem { line: 2, item: '1' }));
+        assert_eq!(solve_first_with::<1>(&vec!["ab"], &Letters, &layout),
+            Err(Error::NoCommonItem { line: 1 }));
+        assert_eq!(solve_second_with::<1>(&vec!["a", "b", "c", "a", "a", "b"], &Letters, &layout),
+            Err(Error::NoCommonItem { line: 1 }));
+    }
 }
 
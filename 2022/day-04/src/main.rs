@@ -1,57 +1,416 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::str::FromStr;
+
 const INPUT_TXT: &str = include_str!("input.txt");
 
 fn main() {
-    let input = read_input(INPUT_TXT);
+    let input = read_input(INPUT_TXT).unwrap();
     println!("Answer to first parts is {}", solve_first(&input));
     println!("Answer to second parts is {}", solve_second(&input));
+    for (relation, count) in histogram(&input) {
+        println!("{:?}: {}", relation, count);
+    }
+    let common = input.iter().filter(|group| group.has_common_section()).count();
+    println!("In {} groups some section is assigned to every elf", common);
+    let (max, runs) = AssignmentIndex::new(&input).max_coverage();
+    println!("Up to {} elves are assigned to the same section in {:?}", max, runs);
+    // --compare A-B,C-D prints how the two assignments relate to each other,
+    // --overlapping A-B lists the assignments sharing sections with A-B
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let option = |name: &str| args.iter().position(|arg| arg == name).map(|i| &args[i + 1..]);
+    if let Some(rest) = option("--compare") {
+        let [text, ..] = rest else {
+            println!("Usage: --compare A-B,C-D");
+            return;
+        };
+        match parse_groups::<i32>(text) {
+            Ok(groups) if groups[0].0.len() == 2 => {
+                let (first, second) = (&groups[0].0[0], &groups[0].0[1]);
+                let relation = first.relation(second);
+                println!("{:?} {:?} {:?}, the other way round {:?}", first, relation, second, relation.inverse());
+                println!("They have {} sections in common", first.overlap_len(second));
+                if is_one_fully_overlaps_other(first, second) {
+                    println!("One of them contains the other");
+                }
+            },
+            Ok(_) => println!("Two assignments are expected"),
+            Err(e) => println!("{}", e),
+        }
+    }
+    if let Some(rest) = option("--overlapping") {
+        let [text, ..] = rest else {
+            println!("Usage: --overlapping A-B");
+            return;
+        };
+        match make_interval::<i32>(text, 1) {
+            Ok(x) => {
+                for id in AssignmentIndex::new(&input).overlapping(&x) {
+                    println!("line {}, elf {}: {:?}", id.line + 1, id.elf + 1, input[id.line].0[id.elf]);
+                }
+            },
+            Err(e) => println!("{}", e),
+        }
+    }
+}
+
+// integer types which can be used to number the sections
+trait Section: Copy + Ord + Debug + FromStr {
+    const MIN: Self;
+    const MAX: Self;
+    // the following section, None for the last one
+    fn next(self) -> Option<Self>;
+    // the preceding section, must not be called for the first one
+    fn prev(self) -> Self;
+    // number of sections from self to last, inclusive
+    fn count_to(self, last: Self) -> u128;
+}
+
+macro_rules! impl_section {
+    ($($t:ty),*) => {$(
+        impl Section for $t {
+            const MIN: $t = <$t>::MIN;
+            const MAX: $t = <$t>::MAX;
+
+            fn next(self) -> Option<$t> {
+                self.checked_add(1)
+            }
+
+            fn prev(self) -> $t {
+                self - 1
+            }
+
+            fn count_to(self, last: $t) -> u128 {
+                (last.abs_diff(self) as u128).saturating_add(1)
+            }
+        }
+    )*};
+}
+
+impl_section!(i8, i16, i32, i64, i128, isize, u8, u16, u32, u64, u128, usize);
+
+// inclusive range of sections, start is never after the finish
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Interval<T = i32>(T, T);
+
+// assignments of the elves from the same line
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct IntervalGroup<T = i32>(Vec<Interval<T>>);
+
+// relation of one interval to another from Allen's interval algebra; every
+// section is a unit cell, so 1-2 meets 3-4 while 1-2 and 2-3 overlap
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+enum Relation {
+    Before,
+    Meets,
+    Overlaps,
+    Starts,
+    During,
+    Finishes,
+    Equals,
+    FinishedBy,
+    Contains,
+    StartedBy,
+    OverlappedBy,
+    MetBy,
+    After,
+}
+
+impl Relation {
+    // relation of the other interval to the first one
+    fn inverse(self) -> Relation {
+        use Relation::*;
+        match self {
+            Before => After,
+            Meets => MetBy,
+            Overlaps => OverlappedBy,
+            Starts => StartedBy,
+            During => Contains,
+            Finishes => FinishedBy,
+            Equals => Equals,
+            FinishedBy => Finishes,
+            Contains => During,
+            StartedBy => Starts,
+            OverlappedBy => Overlaps,
+            MetBy => Meets,
+            After => Before,
+        }
+    }
+
+    // one of the intervals contains the other one
+    fn is_containment(self) -> bool {
+        use Relation::*;
+        matches!(self, Starts | During | Finishes | Equals | FinishedBy | Contains | StartedBy)
+    }
+
+    // the first interval contains the other one, which may be the same
+    fn is_covering(self) -> bool {
+        use Relation::*;
+        matches!(self, Equals | FinishedBy | Contains | StartedBy)
+    }
+
+    // intervals have at least one section in common
+    fn is_overlapping(self) -> bool {
+        use Relation::*;
+        !matches!(self, Before | Meets | MetBy | After)
+    }
 }
 
-#[derive(Clone, Copy)]
-struct Interval(i32, i32);
+impl<T: Section> Interval<T> {
+    fn relation(&self, other: &Interval<T>) -> Relation {
+        use Relation::*;
+        let (Interval(a0, a1), Interval(b0, b1)) = (*self, *other);
+        if a1 < b0 {
+            if a1.next() == Some(b0) { Meets } else { Before }
+        } else if b1 < a0 {
+            if b1.next() == Some(a0) { MetBy } else { After }
+        } else if a0 == b0 && a1 == b1 {
+            Equals
+        } else if a0 == b0 {
+            if a1 < b1 { Starts } else { StartedBy }
+        } else if a1 == b1 {
+            if a0 > b0 { Finishes } else { FinishedBy }
+        } else if b0 < a0 && a1 < b1 {
+            During
+        } else if a0 < b0 && b1 < a1 {
+            Contains
+        } else if a0 < b0 {
+            Overlaps
+        } else {
+            OverlappedBy
+        }
+    }
+
+    // number of sections covered by both intervals
+    fn overlap_len(&self, other: &Interval<T>) -> u128 {
+        let (start, finish) = (self.0.max(other.0), self.1.min(other.1));
+        if start <= finish { start.count_to(finish) } else { 0 }
+    }
+}
+
+impl<T: Section> IntervalGroup<T> {
+    // relations of every interval to each following one in the group
+    fn relations(&self) -> impl Iterator<Item = Relation> + '_ {
+        let intervals = &self.0;
+        (0..intervals.len()).flat_map(move |i| {
+            (i + 1..intervals.len()).map(move |j| intervals[i].relation(&intervals[j]))
+        })
+    }
+
+    // one of the intervals contains all the others
+    fn has_container(&self) -> bool {
+        let intervals = &self.0;
+        (0..intervals.len()).any(|i| {
+            (0..intervals.len()).filter(|&j| j != i).all(|j| intervals[i].relation(&intervals[j]).is_covering())
+        })
+    }
+
+    // at least one section is assigned to every elf of the group; sections of
+    // the intervals are contiguous, so it's enough for every pair to overlap
+    fn has_common_section(&self) -> bool {
+        !self.0.is_empty() && self.relations().all(Relation::is_overlapping)
+    }
 
-#[derive(Clone, Copy)]
-struct IntervalPair(Interval, Interval);
+    // at least two intervals in the group overlap
+    fn has_any_overlap(&self) -> bool {
+        self.relations().any(Relation::is_overlapping)
+    }
+}
 
-fn is_one_fully_overlaps_other(first: &Interval, second: &Interval) -> bool {
-    (first.0 <= second.0 && second.1 <= first.1)
-        || (second.0 <= first.0 && first.1 <= second.1)
+// number of pairs in each relation, groups contribute every pair of their intervals
+fn histogram<T: Section>(input: &Vec<IntervalGroup<T>>) -> BTreeMap<Relation, usize> {
+    let mut res = BTreeMap::new();
+    for group in input {
+        for relation in group.relations() {
+            *res.entry(relation).or_insert(0) += 1;
+        }
+    }
+    return res;
 }
 
-fn solve_first(input: &Vec<IntervalPair>) -> i32 {
+fn is_one_fully_overlaps_other<T: Section>(first: &Interval<T>, second: &Interval<T>) -> bool {
+    first.relation(second).is_containment()
+}
+
+fn solve_first<T: Section>(input: &Vec<IntervalGroup<T>>) -> i32 {
     input.iter()
-        .filter(|IntervalPair(first, second)| is_one_fully_overlaps_other(first, second))
+        .filter(|group| group.has_container())
         .count().try_into().unwrap()
 }
 
-fn is_one_partially_overlaps_other(first: &Interval, second: &Interval) -> bool {
-    let contains = |x, interval: &Interval| interval.0 <= x && x <= interval.1;
-    contains(first.0, second)
-        || contains(first.1, second)
-        || contains(second.0, first)
-        || contains(second.1, first)
+fn is_one_partially_overlaps_other<T: Section>(first: &Interval<T>, second: &Interval<T>) -> bool {
+    first.relation(second).is_overlapping()
 }
 
-fn solve_second(input: &Vec<IntervalPair>) -> i32 {
+fn solve_second<T: Section>(input: &Vec<IntervalGroup<T>>) -> i32 {
     input.iter()
-        .filter(|IntervalPair(first, second)| is_one_partially_overlaps_other(first, second))
+        .filter(|group| group.has_any_overlap())
         .count().try_into().unwrap()
 }
 
-// converts string formatted as "start-finish" into an interval pair
-fn make_interval(s: &str) -> Interval {
-    let ints: Vec<i32> = s.split('-').map(|x| x.parse::<i32>().unwrap()).collect();
-    assert!(ints.len() == 2);
-    assert!(ints[0] <= ints[1]);
-    Interval(ints[0], ints[1])
+// identifies an assignment in the input: line (from 0) and elf in the group (from 0)
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+struct AssignmentId {
+    line: usize,
+    elf: usize,
+}
+
+// static interval tree over all assignments in the input: assignments are sorted
+// by start and the middle of every range is the root of the implicit subtree
+struct AssignmentIndex<T = i32> {
+    items: Vec<(Interval<T>, AssignmentId)>,
+    // max_end[mid] is the highest end among the items of the subtree rooted at mid
+    max_end: Vec<T>,
+}
+
+// position of a sweep line event, End is after the last section
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+enum Position<T> {
+    At(T),
+    End,
 }
 
-fn read_input(file_content: &str) -> Vec<IntervalPair> {
-    file_content.lines().into_iter().map(|line| {
-        let parts: Vec<Interval> = line.split(',').map(|x| make_interval(x)).collect();
-        assert!(parts.len() == 2);
-        IntervalPair(parts[0], parts[1])
+impl<T: Section> AssignmentIndex<T> {
+    fn new(input: &Vec<IntervalGroup<T>>) -> AssignmentIndex<T> {
+        let mut items: Vec<(Interval<T>, AssignmentId)> = input.iter().enumerate()
+            .flat_map(|(line, group)| {
+                group.0.iter().enumerate().map(move |(elf, x)| (*x, AssignmentId { line, elf }))
+            })
+            .collect();
+        items.sort_by_key(|(interval, id)| (interval.0, *id));
+        let mut index = AssignmentIndex { max_end: vec![T::MIN; items.len()], items };
+        index.build(0, index.items.len());
+        return index;
     }
-    ).collect()
+
+    // fills max_end for the subtree over items[lo..hi] and returns its value
+    fn build(&mut self, lo: usize, hi: usize) -> T {
+        if lo >= hi {
+            return T::MIN;
+        }
+        let mid = (lo + hi) / 2;
+        let left = self.build(lo, mid);
+        let right = self.build(mid + 1, hi);
+        self.max_end[mid] = self.items[mid].0.1.max(left).max(right);
+        return self.max_end[mid];
+    }
+
+    // all assignments sharing at least one section with x, ordered by id
+    fn overlapping(&self, x: &Interval<T>) -> Vec<AssignmentId> {
+        let mut res = vec![];
+        self.collect(0, self.items.len(), x, &mut res);
+        res.sort();
+        return res;
+    }
+
+    fn collect(&self, lo: usize, hi: usize, x: &Interval<T>, res: &mut Vec<AssignmentId>) {
+        if lo >= hi {
+            return;
+        }
+        let mid = (lo + hi) / 2;
+        // nothing in the subtree reaches x
+        if self.max_end[mid] < x.0 {
+            return;
+        }
+        self.collect(lo, mid, x, res);
+        let (interval, id) = self.items[mid];
+        // the rest of the subtree starts after x
+        if interval.0 > x.1 {
+            return;
+        }
+        if is_one_partially_overlaps_other(&interval, x) {
+            res.push(id);
+        }
+        self.collect(mid + 1, hi, x, res);
+    }
+
+    // sweeps over the sections and returns every run of sections covered by the
+    // same number of elves, sections covered by nobody are skipped
+    fn coverage(&self) -> Vec<(Interval<T>, usize)> {
+        // start of the interval adds an elf, section after its end removes one
+        let mut events: Vec<(Position<T>, i64)> = self.items.iter()
+            .flat_map(|(interval, _)| [
+                (Position::At(interval.0), 1),
+                (interval.1.next().map_or(Position::End, Position::At), -1),
+            ])
+            .collect();
+        events.sort();
+        let mut res: Vec<(Interval<T>, usize)> = vec![];
+        let mut count = 0;
+        for (i, &(position, delta)) in events.iter().enumerate() {
+            count += delta;
+            let (Position::At(section), Some(&(next, _))) = (position, events.get(i + 1)) else {
+                break;
+            };
+            let last = match next {
+                Position::At(next) if next > section => next.prev(),
+                Position::At(_) => continue,
+                Position::End => T::MAX,
+            };
+            if count == 0 {
+                continue;
+            }
+            let count = count as usize;
+            // merge with the previous run if nothing changed in between
+            match res.last_mut() {
+                Some((prev, prev_count)) if *prev_count == count && prev.1.next() == Some(section) => prev.1 = last,
+                _ => res.push((Interval(section, last), count)),
+            }
+        }
+        return res;
+    }
+
+    // highest number of elves assigned to the same section and all the runs of such sections
+    fn max_coverage(&self) -> (usize, Vec<Interval<T>>) {
+        let coverage = self.coverage();
+        let max = coverage.iter().map(|&(_, count)| count).max().unwrap_or(0);
+        let runs = coverage.into_iter().filter(|&(_, count)| count == max).map(|(interval, _)| interval).collect();
+        (max, runs)
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum Error {
+    // the assignment isn't formatted as "start-finish", lines start from 1
+    InvalidInterval { line: usize, text: String },
+    // start of the assignment is after its finish
+    ReversedInterval { line: usize, text: String },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::InvalidInterval { line, text } => write!(f, "line {}: invalid assignment {:?}", line, text),
+            Error::ReversedInterval { line, text } => write!(f, "line {}: start is after finish in {:?}", line, text),
+        }
+    }
+}
+
+// converts string formatted as "start-finish" into an interval, both ends may be negative
+fn make_interval<T: Section>(s: &str, line: usize) -> Result<Interval<T>, Error> {
+    let invalid = || Error::InvalidInterval { line, text: s.to_string() };
+    // the first char may be a sign of the start
+    let dash = s.char_indices().skip(1).find(|&(_, c)| c == '-').ok_or_else(invalid)?.0;
+    let start = s[..dash].trim().parse::<T>().map_err(|_| invalid())?;
+    let finish = s[dash + 1..].trim().parse::<T>().map_err(|_| invalid())?;
+    if start > finish {
+        return Err(Error::ReversedInterval { line, text: s.to_string() });
+    }
+    Ok(Interval(start, finish))
+}
+
+// reads comma separated assignments, any number of them per line
+fn parse_groups<T: Section>(file_content: &str) -> Result<Vec<IntervalGroup<T>>, Error> {
+    file_content.lines().enumerate().map(|(i, line)| {
+        let intervals = line.split(',')
+            .map(|x| make_interval(x, i + 1))
+            .collect::<Result<Vec<Interval<T>>, Error>>()?;
+        Ok(IntervalGroup(intervals))
+    }).collect()
+}
+
+fn read_input(file_content: &str) -> Result<Vec<IntervalGroup>, Error> {
+    parse_groups::<i32>(file_content)
 }
 
 
@@ -97,13 +456,13 @@ mod tests {
 
     #[test]
     fn part1() {
-        let input = read_input(TEST_INPUT_TXT);
+        let input = read_input(TEST_INPUT_TXT).unwrap();
         assert_eq!(solve_first(&input), 2);
     }
 
     #[test]
     fn part1_wrong_submissions() {
-        let input = read_input(INPUT_TXT);
+        let input = read_input(INPUT_TXT).unwrap();
         assert!(solve_first(&input) > 476);
     }
 
@@ -142,9 +501,127 @@ mod tests {
         assert!(is_one_partially_overlaps_other(&second, &first));
     }
 
+    #[test]
+    fn relations() {
+        use Relation::*;
+        let cases = [
+            (Interval(1, 2), Interval(4, 5), Before),
+            (Interval(1, 2), Interval(3, 5), Meets),
+            (Interval(1, 3), Interval(3, 5), Overlaps),
+            (Interval(3, 4), Interval(3, 5), Starts),
+            (Interval(4, 4), Interval(3, 5), During),
+            (Interval(4, 5), Interval(3, 5), Finishes),
+            (Interval(3, 5), Interval(3, 5), Equals),
+            (Interval(6, 6), Interval(6, 6), Equals),
+        ];
+        for (first, second, want) in cases {
+            assert_eq!(first.relation(&second), want);
+            assert_eq!(second.relation(&first), want.inverse());
+            assert_eq!(want.inverse().inverse(), want);
+        }
+        assert_eq!(Interval(1, 3).overlap_len(&Interval(3, 5)), 1);
+        assert_eq!(Interval(1, 9).overlap_len(&Interval(3, 5)), 3);
+        assert_eq!(Interval(1, 2).overlap_len(&Interval(3, 5)), 0);
+        assert_eq!(Interval(1, 2).overlap_len(&Interval(7, 9)), 0);
+    }
+
+    #[test]
+    fn relations_histogram() {
+        use Relation::*;
+        let input = read_input(TEST_INPUT_TXT).unwrap();
+        let want = BTreeMap::from([(Before, 1), (Meets, 1), (Overlaps, 2), (Finishes, 1), (Contains, 1)]);
+        assert_eq!(histogram(&input), want);
+    }
+
+    #[test]
+    fn assignment_index() {
+        let input = read_input(TEST_INPUT_TXT).unwrap();
+        let index = AssignmentIndex::new(&input);
+        let id = |line, elf| AssignmentId { line, elf };
+        assert_eq!(index.overlapping(&Interval(9, 9)), vec![id(2, 1)]);
+        assert_eq!(index.overlapping(&Interval(1, 1)), vec![]);
+        assert_eq!(index.overlapping(&Interval(8, 8)), vec![id(0, 1), id(2, 1), id(3, 0), id(5, 1)]);
+        assert_eq!(index.overlapping(&Interval(0, 100)).len(), 12);
+        // every query matches the pairwise check
+        for x in [Interval(1, 3), Interval(4, 4), Interval(5, 6), Interval(7, 10)] {
+            let want: Vec<AssignmentId> = input.iter().enumerate()
+                .flat_map(|(line, group)| [(line, 0, group.0[0]), (line, 1, group.0[1])])
+                .filter(|(_, _, interval)| is_one_partially_overlaps_other(interval, &x))
+                .map(|(line, elf, _)| id(line, elf))
+                .collect();
+            assert_eq!(index.overlapping(&x), want);
+        }
+    }
+
+    #[test]
+    fn max_coverage() {
+        let input = read_input(TEST_INPUT_TXT).unwrap();
+        let index = AssignmentIndex::new(&input);
+        assert_eq!(index.max_coverage(), (8, vec![Interval(6, 6)]));
+        let coverage = index.coverage();
+        assert_eq!(coverage.first(), Some(&(Interval(2, 2), 4)));
+        assert_eq!(coverage.last(), Some(&(Interval(9, 9), 1)));
+        // gaps are skipped
+        let input = read_input("1-2,1-3\n7-8,8-9").unwrap();
+        let index = AssignmentIndex::new(&input);
+        assert_eq!(index.coverage(), vec![
+            (Interval(1, 2), 2), (Interval(3, 3), 1), (Interval(7, 7), 1), (Interval(8, 8), 2), (Interval(9, 9), 1),
+        ]);
+        assert_eq!(index.max_coverage(), (2, vec![Interval(1, 2), Interval(8, 8)]));
+        assert_eq!(AssignmentIndex::<i32>::new(&vec![]).max_coverage(), (0, vec![]));
+    }
+
+    #[test]
+    fn groups() {
+        let input = read_input("1-9,2-3,4-8\n1-2,3-4,5-6\n1-2,4-5,2-3\n1-5,2-6,6-9\n5-5").unwrap();
+        let has_container: Vec<bool> = input.iter().map(|g| g.has_container()).collect();
+        assert_eq!(has_container, vec![true, false, false, false, true]);
+        let has_any_overlap: Vec<bool> = input.iter().map(|g| g.has_any_overlap()).collect();
+        assert_eq!(has_any_overlap, vec![true, false, true, true, false]);
+        let has_common_section: Vec<bool> = input.iter().map(|g| g.has_common_section()).collect();
+        assert_eq!(has_common_section, vec![false, false, false, false, true]);
+        assert_eq!(solve_first(&input), 2);
+        assert_eq!(solve_second(&input), 3);
+        assert_eq!(histogram(&input).values().sum::<usize>(), 3 * 4);
+        assert!(read_input("2-3,2-4,1-5").unwrap()[0].has_common_section());
+        // either of the same intervals contains the others
+        assert!(read_input("2-4,3-4,2-4").unwrap()[0].has_container());
+        assert!(!read_input("1-3,2-5,4-6").unwrap()[0].has_common_section());
+    }
+
+    #[test]
+    fn other_section_types() {
+        let input = parse_groups::<u8>("0-255,255-255\n0-10,11-12").unwrap();
+        assert_eq!(solve_first(&input), 1);
+        assert_eq!(input[1].0[0].relation(&input[1].0[1]), Relation::Meets);
+        assert_eq!(input[0].0[0].overlap_len(&input[0].0[0]), 256);
+        let index = AssignmentIndex::new(&input);
+        assert_eq!(index.max_coverage(), (2, vec![Interval(0, 12), Interval(255, 255)]));
+        assert_eq!(index.coverage().last(), Some(&(Interval(255, 255), 2)));
+        let input = parse_groups::<i64>("-10--5,-5-3\n-3-3,-1-0").unwrap();
+        assert_eq!(input[0].0[0], Interval(-10, -5));
+        assert_eq!(input[0].0[1], Interval(-5, 3));
+        assert_eq!(solve_second(&input), 2);
+        assert_eq!(AssignmentIndex::new(&input).overlapping(&Interval(-7, -6)), vec![AssignmentId { line: 0, elf: 0 }]);
+        // the sweep ends after the last section
+        let input = parse_groups::<i8>("-128-127,127-127").unwrap();
+        assert_eq!(AssignmentIndex::new(&input).coverage(), vec![(Interval(-128, 126), 1), (Interval(127, 127), 2)]);
+    }
+
+    #[test]
+    fn parse_errors() {
+        let text = |s: &str| s.to_string();
+        assert_eq!(read_input("1-2,3-4\n1-2,x-4"), Err(Error::InvalidInterval { line: 2, text: text("x-4") }));
+        assert_eq!(read_input("1-2,4-3"), Err(Error::ReversedInterval { line: 1, text: text("4-3") }));
+        assert_eq!(read_input("1-2,3"), Err(Error::InvalidInterval { line: 1, text: text("3") }));
+        assert_eq!(read_input("1-2\n"), Ok(vec![IntervalGroup(vec![Interval(1, 2)])]));
+        assert_eq!(read_input("1-2\n\n"), Err(Error::InvalidInterval { line: 2, text: text("") }));
+        assert_eq!(parse_groups::<u8>("1-256"), Err(Error::InvalidInterval { line: 1, text: text("1-256") }));
+    }
+
     #[test]
     fn part2() {
-        let input = read_input(TEST_INPUT_TXT);
+        let input = read_input(TEST_INPUT_TXT).unwrap();
         assert_eq!(solve_second(&input), 4);
     }
 }
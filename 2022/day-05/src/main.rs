@@ -1,89 +1,385 @@
-use regex::Regex;
+mod parser;
+
+use parser::{parse, parse_drawing, parse_instruction, ParseError};
 
 const INPUT_TXT: &str = include_str!("input.txt");
 
 fn main() {
     let input = read_input(INPUT_TXT);
     println!("Answer to first parts is {}", solve_first(input.clone()));
-    println!("Answer to second parts is {}", solve_second(input));
+    println!("Answer to second parts is {}", solve_second(input.clone()));
+    let args: Vec<String> = std::env::args().collect();
+    let option = |name: &str| args.iter().position(|arg| arg == name).map(|i| &args[i + 1..]);
+    // rearranges with a crane lifting at most the given number of crates at once
+    if let Some(rest) = option("--capacity") {
+        match rest.first().map(|x| x.parse::<usize>()) {
+            Some(Ok(capacity)) if capacity > 0 => {
+                println!("Answer for a crane lifting {} crates is {}", capacity, rearrange(input.clone(), &LimitedCrane(capacity)));
+            },
+            _ => println!("Usage: --capacity N, where the crane lifts N > 0 crates at once"),
+        }
+    }
+    // prints the stacks after each instruction of the first part
+    if option("--steps").is_some() {
+        for drawing in render_steps(input.clone(), &CrateMover9000) {
+            println!("{}", drawing);
+        }
+    }
+    let first_part_history = || {
+        let (stacks, instructions) = input.clone();
+        let mut history = History::new(stacks);
+        for action in instructions {
+            history.apply(action, &CrateMover9000).unwrap();
+        }
+        history
+    };
+    // saves the history of the first part to a file to be replayed later
+    if let Some([path, ..]) = option("--history") {
+        std::fs::write(path, first_part_history().save()).unwrap();
+    }
+    // prints the step of the first part after which the crate gets to the stack
+    if let Some([label, stack, ..]) = option("--arrival") {
+        match first_part_history().arrival(label, stack.parse().unwrap()) {
+            Some(step) => println!("{} is on stack {} after step {}", label, stack, step),
+            None => println!("{} never gets to stack {}", label, stack),
+        }
+    }
+    // prints the stacks from the saved history after the given number of steps
+    if let Some([path, step, ..]) = option("--replay") {
+        let mut history = History::load(&std::fs::read_to_string(path).unwrap()).unwrap();
+        history.jump(step.parse().unwrap());
+        print!("{}", render(&history.stacks));
+    }
 }
 
-// alias to handle array of stacks
-type Stacks = Vec<Vec<char>>;
+// alias to handle array of stacks, crate labels may be longer than one char
+type Stacks = Vec<Vec<String>>;
 
 // stores instruction in form of (count, from, to)
-#[derive(Clone, Copy)]
-struct Instruction(i32, usize, usize);
-
-impl Instruction {
-    fn make(line: &str) -> Option<Instruction> {
-        let re: Regex = Regex::new(r"^move (\d+) from (\d+) to (\d+)$").unwrap();
-        let parameters: Vec<i32> = re.captures(line)?
-            .iter().skip(1).map(|x| x.unwrap().as_str().parse::<i32>().unwrap())
-            .collect();
-        if parameters.len() != 3 {
-            None
-        } else {
-            Some(Instruction(parameters[0], parameters[1] as usize, parameters[2] as usize))
-        }
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+struct Instruction(usize, usize, usize);
+
+impl std::fmt::Display for Instruction {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "move {} from {} to {}", self.0, self.1, self.2)
     }
 }
 
 // tuple alias
 type Input = (Stacks, Vec<Instruction>);
 
-fn move_reversed(s: &mut Stacks, action: Instruction) {
-    let Instruction(mut count, from, to) = action;
-    while count != 0 {
-        let c = s[from].pop().unwrap();
-        s[to].push(c);
-        count -= 1;
+// takes `count` crates from the top of the stack keeping their order
+fn lift(s: &mut Stacks, from: usize, count: usize) -> Vec<String> {
+    let height = s[from].len();
+    s[from].split_off(height - count)
+}
+
+// model of the crane operating the stacks
+trait Crane {
+    fn apply(&self, s: &mut Stacks, action: Instruction);
+}
+
+// moves crates one at a time, so they land in reverse order
+struct CrateMover9000;
+
+impl Crane for CrateMover9000 {
+    fn apply(&self, s: &mut Stacks, action: Instruction) {
+        let Instruction(count, from, to) = action;
+        if from == to {
+            return;
+        }
+        let mut crates = lift(s, from, count);
+        crates.reverse();
+        s[to].extend(crates);
     }
 }
 
-fn solve_first(input: Input) -> String {
-    let (mut stacks, instructions) = input;
-    for action in instructions {
-        move_reversed(&mut stacks, action);
+// moves all crates at once, so they keep their order
+struct CrateMover9001;
+
+impl Crane for CrateMover9001 {
+    fn apply(&self, s: &mut Stacks, action: Instruction) {
+        let Instruction(count, from, to) = action;
+        if from == to {
+            return;
+        }
+        let crates = lift(s, from, count);
+        s[to].extend(crates);
     }
-    stacks.iter().skip(1).map(|x| x.last().unwrap()).collect()
 }
 
-fn move_ordered(s: &mut Stacks, action: Instruction) {
-    let Instruction(mut count, from, to) = action;
-    let pos = s[to].len();
-    while count != 0 {
-        let c = s[from].pop().unwrap();
-        s[to].insert(pos, c);
-        count -= 1;
+// moves at most `capacity` crates per lift keeping their order within the lift,
+// capacity of 1 works as CrateMover9000
+struct LimitedCrane(usize);
+
+impl Crane for LimitedCrane {
+    fn apply(&self, s: &mut Stacks, action: Instruction) {
+        let Instruction(count, from, to) = action;
+        assert!(self.0 > 0, "crane must lift at least one crate");
+        if from == to {
+            return;
+        }
+        let mut left = count;
+        while left != 0 {
+            let lifted = left.min(self.0);
+            let crates = lift(s, from, lifted);
+            s[to].extend(crates);
+            left -= lifted;
+        }
     }
 }
 
+#[derive(Debug, PartialEq, Eq)]
+enum Error {
+    // stack doesn't exist, instructions are counted from 1
+    UnknownStack { instruction: usize, stack: usize },
+    // stack has fewer crates than the instruction moves
+    NotEnoughCrates { instruction: usize, stack: usize, height: usize, count: usize },
+    // line of a saved history can't be read, lines are counted from 1
+    InvalidHistory { line: usize },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::UnknownStack { instruction, stack } =>
+                write!(f, "instruction {}: no stack {}", instruction, stack),
+            Error::NotEnoughCrates { instruction, stack, height, count } =>
+                write!(f, "instruction {}: can't move {} crates from stack {} of height {}", instruction, count, stack, height),
+            Error::InvalidHistory { line } => write!(f, "line {}: invalid history entry", line),
+        }
+    }
+}
+
+// what to do with invalid instructions
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Mode {
+    // stop at the first invalid instruction
+    Strict,
+    // skip invalid instructions and carry on
+    Lenient,
+}
+
+// checks that the instruction can be applied to the stacks, `instruction` is used for reporting
+fn validate(s: &Stacks, action: Instruction, instruction: usize) -> Result<(), Error> {
+    let Instruction(count, from, to) = action;
+    // stack 0 is a placeholder, real stacks start from 1
+    for stack in [from, to] {
+        if stack == 0 || stack >= s.len() {
+            return Err(Error::UnknownStack { instruction, stack });
+        }
+    }
+    let height = s[from].len();
+    if height < count {
+        return Err(Error::NotEnoughCrates { instruction, stack: from, height, count });
+    }
+    Ok(())
+}
+
+// applies the valid instructions, in lenient mode returns errors for the skipped ones
+fn execute(s: &mut Stacks, instructions: &[Instruction], crane: &impl Crane, mode: Mode) -> Result<Vec<Error>, Error> {
+    let mut skipped = vec![];
+    for (i, &action) in instructions.iter().enumerate() {
+        match validate(s, action, i + 1) {
+            Ok(()) => crane.apply(s, action),
+            Err(e) if mode == Mode::Lenient => skipped.push(e),
+            Err(e) => return Err(e),
+        }
+    }
+    return Ok(skipped);
+}
+
+// applies all instructions and returns the top crates, empty stacks are skipped
+fn rearrange(input: Input, crane: &impl Crane) -> String {
+    let (mut stacks, instructions) = input;
+    execute(&mut stacks, &instructions, crane, Mode::Strict).unwrap();
+    stacks.iter().skip(1).filter_map(|x| x.last()).map(|x| x.as_str()).collect()
+}
+
+fn solve_first(input: Input) -> String {
+    rearrange(input, &CrateMover9000)
+}
+
 fn solve_second(input: Input) -> String {
+    rearrange(input, &CrateMover9001)
+}
+
+// draws the stacks the same way as in the puzzle input, including the line with numbers;
+// columns are wide enough to fit the longest label and the number of the stack
+fn render(s: &Stacks) -> String {
+    let height = s.iter().map(|x| x.len()).max().unwrap_or(0);
+    let widths: Vec<usize> = s.iter().enumerate().skip(1)
+        .map(|(i, x)| {
+            let label = x.iter().map(|c| c.chars().count() + 2).max().unwrap_or(3);
+            label.max(i.to_string().len())
+        })
+        .collect();
+    let mut lines: Vec<String> = vec![];
+    for level in (0..height).rev() {
+        let cells: Vec<String> = s.iter().skip(1).zip(&widths)
+            .map(|(x, &w)| format!("{:^w$}", x.get(level).map_or(String::new(), |c| format!("[{}]", c))))
+            .collect();
+        lines.push(cells.join(" "));
+    }
+    let numbers: Vec<String> = widths.iter().enumerate().map(|(i, &w)| format!("{:^w$}", i + 1)).collect();
+    lines.push(numbers.join(" "));
+    lines.join("\n") + "\n"
+}
+
+// drawings of the stacks before and after every instruction
+fn render_steps(input: Input, crane: &impl Crane) -> Vec<String> {
     let (mut stacks, instructions) = input;
-    for action in instructions {
-        move_ordered(&mut stacks, action);
+    let mut res = vec![render(&stacks)];
+    for (i, &action) in instructions.iter().enumerate() {
+        validate(&stacks, action, i + 1).unwrap();
+        crane.apply(&mut stacks, action);
+        res.push(format!("{}\n\n{}", action, render(&stacks)));
     }
-    stacks.iter().skip(1).map(|x| x.last().unwrap()).collect()
+    return res;
 }
 
-fn read_input(file_content: &str) -> Input {
-    let mut lines = file_content.lines();
-    let dwg = lines.by_ref().take_while(|&x| !x.is_empty()).collect::<Vec<&str>>();
-    let sz = dwg[0].len();
-    let dwg_width = (sz + 1) / 4 + 1;
-    let mut s: Stacks = vec![vec![]; dwg_width];
-    // skip line with numbers
-    for &line in dwg.iter().rev().skip(1) {
-        for (j, c) in line.chars().enumerate().skip(1).step_by(4) {
-            if c != ' ' {
-                s[j / 4 + 1].push(c);
+// applied instruction with the crates it moved, enough to undo and redo it
+// without knowing the crane
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Operation {
+    action: Instruction,
+    // crates taken from the source stack, bottom first
+    lifted: Vec<String>,
+    // crates as they landed on the destination stack, bottom first
+    landed: Vec<String>,
+}
+
+// log of the applied instructions which allows to move back and forth in time
+struct History {
+    initial: Stacks,
+    stacks: Stacks,
+    log: Vec<Operation>,
+    // number of operations from the log applied to the stacks
+    position: usize,
+}
+
+impl History {
+    fn new(stacks: Stacks) -> History {
+        History { initial: stacks.clone(), stacks, log: vec![], position: 0 }
+    }
+
+    // applies the instruction after the current position, the undone operations are dropped
+    fn apply(&mut self, action: Instruction, crane: &impl Crane) -> Result<(), Error> {
+        validate(&self.stacks, action, self.position + 1)?;
+        let Instruction(count, from, to) = action;
+        let lifted = self.stacks[from][self.stacks[from].len() - count..].to_vec();
+        crane.apply(&mut self.stacks, action);
+        let landed = if from == to { lifted.clone() } else { self.stacks[to][self.stacks[to].len() - count..].to_vec() };
+        self.log.truncate(self.position);
+        self.log.push(Operation { action, lifted, landed });
+        self.position += 1;
+        Ok(())
+    }
+
+    // returns false if there is nothing to undo
+    fn undo(&mut self) -> bool {
+        if self.position == 0 {
+            return false;
+        }
+        self.position -= 1;
+        let Operation { action: Instruction(count, from, to), lifted, .. } = &self.log[self.position];
+        if from != to {
+            let height = self.stacks[*to].len();
+            self.stacks[*to].truncate(height - count);
+            self.stacks[*from].extend(lifted.iter().cloned());
+        }
+        return true;
+    }
+
+    // returns false if there is nothing to redo
+    fn redo(&mut self) -> bool {
+        if self.position == self.log.len() {
+            return false;
+        }
+        let Operation { action: Instruction(count, from, to), landed, .. } = &self.log[self.position];
+        if from != to {
+            let height = self.stacks[*from].len();
+            self.stacks[*from].truncate(height - count);
+            self.stacks[*to].extend(landed.iter().cloned());
+        }
+        self.position += 1;
+        return true;
+    }
+
+    // moves to the state after `step` operations, stops at the end of the log
+    fn jump(&mut self, step: usize) {
+        while self.position > step && self.undo() {}
+        while self.position < step && self.redo() {}
+    }
+
+    // first step after which a crate with the label is on the stack, 0 if it's there from the start;
+    // only the steps up to the current position count, the undone ones haven't happened
+    fn arrival(&self, label: &str, stack: usize) -> Option<usize> {
+        if self.initial.get(stack).is_some_and(|x| x.iter().any(|c| c == label)) {
+            return Some(0);
+        }
+        self.log[..self.position].iter()
+            .position(|op| op.action.2 == stack && op.landed.iter().any(|c| c == label))
+            .map(|i| i + 1)
+    }
+
+    // text form of the history: the initial drawing, an empty line, current position
+    // and every operation as "move 2 from 1 to 3: A B / B A", where crates are listed
+    // as lifted and as landed
+    fn save(&self) -> String {
+        let mut res = render(&self.initial);
+        res += &format!("\nat {}\n", self.position);
+        for op in &self.log {
+            res += &format!("{}: {} / {}\n", op.action, op.lifted.join(" "), op.landed.join(" "));
+        }
+        return res;
+    }
+
+    // reads the history written by `save`
+    fn load(content: &str) -> Result<History, Error> {
+        let lines: Vec<&str> = content.lines().collect();
+        let dwg_len = lines.iter().position(|x| x.is_empty()).ok_or(Error::InvalidHistory { line: lines.len() + 1 })?;
+        let stacks = parse_drawing(&lines[..dwg_len], 1).map_err(|e| match e {
+            ParseError::Drawing { line, .. } | ParseError::Instruction { line, .. } => Error::InvalidHistory { line },
+        })?;
+        let mut history = History::new(stacks);
+        // numbers of the lines being parsed
+        let at_line = dwg_len + 2;
+        let position = lines.get(at_line - 1)
+            .and_then(|x| x.strip_prefix("at "))
+            .and_then(|x| x.parse::<usize>().ok())
+            .ok_or(Error::InvalidHistory { line: at_line })?;
+        for (i, &line) in lines.iter().enumerate().skip(at_line) {
+            let invalid = || Error::InvalidHistory { line: i + 1 };
+            let (action, crates) = line.split_once(": ").ok_or_else(invalid)?;
+            let action = parse_instruction(action, i + 1).map_err(|_| invalid())?;
+            let (lifted, landed) = crates.split_once(" /").ok_or_else(invalid)?;
+            let labels = |x: &str| x.split_whitespace().map(|c| c.to_string()).collect::<Vec<String>>();
+            let (lifted, landed) = (labels(lifted), labels(landed));
+            // the operation must take the crates which are on top of the stack
+            // and can only change their order
+            let Instruction(count, from, _) = action;
+            let sorted = |x: &Vec<String>| { let mut x = x.clone(); x.sort(); x };
+            let is_valid = validate(&history.stacks, action, i + 1).is_ok()
+                && lifted.len() == count && sorted(&lifted) == sorted(&landed)
+                && history.stacks[from].ends_with(&lifted);
+            if !is_valid {
+                return Err(invalid());
             }
+            // replay the operation to restore the state
+            history.log.push(Operation { action, lifted, landed });
+            history.redo();
         }
+        if position > history.log.len() {
+            return Err(Error::InvalidHistory { line: at_line });
+        }
+        history.jump(position);
+        return Ok(history);
     }
-    let instructions = lines.map(|x| { 
-        Instruction::make(x).unwrap()}).collect();
-    return (s, instructions)
+}
+
+fn read_input(file_content: &str) -> Input {
+    parse(file_content).unwrap()
 }
 
 
@@ -92,6 +388,11 @@ mod tests {
     use crate::*;
     const TEST_INPUT_TXT: &str = include_str!("input_test.txt");
 
+    // stack of single char crates
+    fn crates(labels: &str) -> Vec<String> {
+        labels.chars().map(|c| c.to_string()).collect()
+    }
+
     #[test]
     fn part1() {
         let input = read_input(TEST_INPUT_TXT);
@@ -103,5 +404,166 @@ mod tests {
         let input = read_input(TEST_INPUT_TXT);
         assert_eq!(solve_second(input), "MCD");
     }
+
+    #[test]
+    fn cranes() {
+        let stacks: Stacks = vec![vec![], crates("ABCDE"), vec![]];
+        let action = Instruction(5, 1, 2);
+        let run = |crane: &dyn Crane| {
+            let mut s = stacks.clone();
+            crane.apply(&mut s, action);
+            s
+        };
+        assert_eq!(run(&CrateMover9000)[2], crates("EDCBA"));
+        assert_eq!(run(&CrateMover9001)[2], crates("ABCDE"));
+        assert_eq!(run(&LimitedCrane(2))[2], crates("DEBCA"));
+        assert_eq!(run(&LimitedCrane(1))[2], run(&CrateMover9000)[2]);
+        assert_eq!(run(&LimitedCrane(5))[2], run(&CrateMover9001)[2]);
+        assert!(run(&LimitedCrane(3))[1].is_empty());
+        // moving onto the same stack changes nothing
+        let mut s = stacks.clone();
+        CrateMover9000.apply(&mut s, Instruction(3, 1, 1));
+        assert_eq!(s, stacks);
+        let input = read_input(TEST_INPUT_TXT);
+        assert_eq!(rearrange(input, &LimitedCrane(2)), "MCZ");
+        // the first stack ends up empty
+        let (stacks, _) = read_input(TEST_INPUT_TXT);
+        assert_eq!(rearrange((stacks, vec![Instruction(2, 1, 3)]), &CrateMover9001), "DN");
+    }
+
+    #[test]
+    fn rendering() {
+        let (stacks, _) = read_input(TEST_INPUT_TXT);
+        let drawing = render(&stacks);
+        assert!(TEST_INPUT_TXT.starts_with(&drawing));
+        // parsing the drawing gives the same stacks
+        let (parsed, instructions) = read_input(&drawing);
+        assert_eq!(parsed, stacks);
+        assert!(instructions.is_empty());
+        let stacks: Stacks = vec![vec![], vec![], crates("ABC"), vec![], crates("D")];
+        assert_eq!(read_input(&render(&stacks)).0, stacks);
+        assert_eq!(render(&stacks), "    [C]        \n    [B]        \n    [A]     [D]\n 1   2   3   4 \n");
+        // labels of any length
+        let mut stacks: Stacks = vec![vec![]; 12];
+        stacks[1] = vec!["AB".to_string(), "C".to_string()];
+        stacks[11] = vec!["LONG".to_string()];
+        assert_eq!(read_input(&render(&stacks)).0, stacks);
+    }
+
+    #[test]
+    fn rendering_steps() {
+        let input = read_input(TEST_INPUT_TXT);
+        let steps = render_steps(input.clone(), &CrateMover9001);
+        assert_eq!(steps.len(), input.1.len() + 1);
+        assert_eq!(steps[1], "move 1 from 2 to 1\n\n[D]        \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n");
+        let (mut stacks, instructions) = input;
+        execute(&mut stacks, &instructions, &CrateMover9001, Mode::Strict).unwrap();
+        assert!(steps.last().unwrap().ends_with(&render(&stacks)));
+    }
+
+    #[test]
+    fn history() {
+        let (stacks, instructions) = read_input(TEST_INPUT_TXT);
+        let mut history = History::new(stacks.clone());
+        let mut states = vec![stacks.clone()];
+        for &action in &instructions {
+            history.apply(action, &CrateMover9000).unwrap();
+            states.push(history.stacks.clone());
+        }
+        assert_eq!(solve_first((stacks.clone(), instructions.clone())),
+            history.stacks.iter().skip(1).map(|x| x.last().unwrap().as_str()).collect::<String>());
+        assert!(!history.redo());
+        // walk back and forth through every step
+        for step in (0..states.len()).rev() {
+            history.jump(step);
+            assert_eq!(history.stacks, states[step]);
+        }
+        assert!(!history.undo());
+        for state in states.iter().skip(1) {
+            assert!(history.redo());
+            assert_eq!(&history.stacks, state);
+        }
+        history.jump(100);
+        assert_eq!(history.position, instructions.len());
+        // 'D' starts on stack 2, gets to stack 1 with the first move and to 3 with the second one
+        assert_eq!(history.arrival("D", 2), Some(0));
+        assert_eq!(history.arrival("D", 1), Some(1));
+        assert_eq!(history.arrival("D", 3), Some(2));
+        assert_eq!(history.arrival("X", 3), None);
+        // the second move is undone
+        history.jump(1);
+        assert_eq!(history.arrival("D", 1), Some(1));
+        assert_eq!(history.arrival("D", 3), None);
+        // new instruction drops the undone ones
+        history.jump(1);
+        history.apply(Instruction(1, 1, 3), &CrateMover9001).unwrap();
+        assert_eq!(history.log.len(), 2);
+        assert!(!history.redo());
+        assert_eq!(history.apply(Instruction(5, 1, 3), &CrateMover9001), Err(Error::NotEnoughCrates { instruction: 3, stack: 1, height: 2, count: 5 }));
+    }
+
+    #[test]
+    fn history_saving() {
+        let (stacks, instructions) = read_input(TEST_INPUT_TXT);
+        let mut history = History::new(stacks);
+        for &action in &instructions {
+            history.apply(action, &LimitedCrane(2)).unwrap();
+        }
+        history.jump(3);
+        let saved = history.save();
+        assert!(saved.contains("\nat 3\nmove 1 from 2 to 1: D / D\nmove 3 from 1 to 3: Z N D / N D Z\n"));
+        let mut loaded = History::load(&saved).unwrap();
+        assert_eq!(loaded.stacks, history.stacks);
+        assert_eq!(loaded.log, history.log);
+        assert_eq!(loaded.save(), saved);
+        loaded.jump(4);
+        history.jump(4);
+        assert_eq!(loaded.stacks, history.stacks);
+        // broken entries
+        let broken = saved.replace("move 3 from 1 to 3: Z N D", "move 3 from 1 to 3: Z N");
+        assert_eq!(History::load(&broken).err(), Some(Error::InvalidHistory { line: 8 }));
+        let broken = saved.replace("N D / N D Z", "N D / N D X");
+        assert_eq!(History::load(&broken).err(), Some(Error::InvalidHistory { line: 8 }));
+        let broken = saved.replace("N D / N D Z", "N D / N D D");
+        assert_eq!(History::load(&broken).err(), Some(Error::InvalidHistory { line: 8 }));
+        let broken = saved.replace("at 3", "at 9");
+        assert_eq!(History::load(&broken).err(), Some(Error::InvalidHistory { line: 6 }));
+        let broken = saved.replace("move 1 from 2 to 1", "move 1 from 3 to 1");
+        assert_eq!(History::load(&broken).err(), Some(Error::InvalidHistory { line: 7 }));
+        let broken = saved.replace("move 1 from 2 to 1", "move 1 from 4 to 1");
+        assert_eq!(History::load(&broken).err(), Some(Error::InvalidHistory { line: 7 }));
+        let path = std::env::temp_dir().join("day-05-history.txt");
+        std::fs::write(&path, &saved).unwrap();
+        let loaded = History::load(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(loaded.position, 3);
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn validation() {
+        let stacks: Stacks = vec![vec![], crates("AB"), crates("C")];
+        let instructions = vec![
+            Instruction(1, 1, 2),
+            Instruction(2, 2, 3),
+            Instruction(3, 1, 2),
+            Instruction(1, 0, 1),
+            Instruction(2, 2, 1),
+        ];
+        let mut s = stacks.clone();
+        assert_eq!(execute(&mut s, &instructions, &CrateMover9000, Mode::Strict),
+            Err(Error::UnknownStack { instruction: 2, stack: 3 }));
+        assert_eq!(s, vec![vec![], crates("A"), crates("CB")]);
+        let mut s = stacks.clone();
+        let skipped = execute(&mut s, &instructions, &CrateMover9000, Mode::Lenient).unwrap();
+        assert_eq!(skipped, vec![
+            Error::UnknownStack { instruction: 2, stack: 3 },
+            Error::NotEnoughCrates { instruction: 3, stack: 1, height: 1, count: 3 },
+            Error::UnknownStack { instruction: 4, stack: 0 },
+        ]);
+        assert_eq!(s, vec![vec![], crates("ABC"), vec![]]);
+        // moving nothing from an empty stack is fine
+        let mut s = stacks.clone();
+        assert_eq!(execute(&mut s, &[Instruction(0, 2, 1), Instruction(0, 2, 1)], &CrateMover9001, Mode::Strict), Ok(vec![]));
+    }
 }
 
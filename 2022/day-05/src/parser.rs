@@ -0,0 +1,193 @@
+use crate::{Input, Instruction, Stacks};
+
+// position of the malformed part of the input, lines and columns start from 1
+#[derive(Debug, PartialEq, Eq)]
+pub enum ParseError {
+    Drawing { line: usize, column: usize },
+    Instruction { line: usize, column: usize },
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            ParseError::Drawing { line, column } => write!(f, "{}:{}: malformed drawing", line, column),
+            ParseError::Instruction { line, column } => write!(f, "{}:{}: malformed instruction", line, column),
+        }
+    }
+}
+
+// splits the line into tokens separated by spaces, returns (first char, last char, token)
+// where positions are in chars from 0
+fn tokens(line: &str) -> Vec<(usize, usize, String)> {
+    let mut res = vec![];
+    let mut current: Option<(usize, String)> = None;
+    for (i, c) in line.chars().chain(std::iter::once(' ')).enumerate() {
+        match (c, current.as_mut()) {
+            (' ', Some(_)) => {
+                let (start, token) = current.take().unwrap();
+                res.push((start, i - 1, token));
+            }
+            (' ', None) => {}
+            (_, Some((_, token))) => token.push(c),
+            (_, None) => current = Some((i, c.to_string())),
+        }
+    }
+    return res;
+}
+
+// parses the drawing of the stacks, `first_line` is the number of its first line;
+// the last line holds the numbers of the stacks and every crate in brackets belongs
+// to the stack whose number is right below it, so columns may have any width and
+// labels may have any length
+pub fn parse_drawing(dwg: &[&str], first_line: usize) -> Result<Stacks, ParseError> {
+    let (&footer, rows) = dwg.split_last().ok_or(ParseError::Drawing { line: first_line, column: 1 })?;
+    let footer_line = first_line + rows.len();
+    // (first char, last char) of every stack number, stack 0 is a placeholder
+    let mut columns = vec![];
+    for (start, end, token) in tokens(footer) {
+        if token.parse::<usize>() != Ok(columns.len() + 1) {
+            return Err(ParseError::Drawing { line: footer_line, column: start + 1 });
+        }
+        columns.push((start, end));
+    }
+    let mut s: Stacks = vec![vec![]; columns.len() + 1];
+    for (i, &row) in rows.iter().enumerate().rev() {
+        let line = first_line + i;
+        for (start, end, token) in tokens(row) {
+            let error = ParseError::Drawing { line, column: start + 1 };
+            let label = token.strip_prefix('[').and_then(|x| x.strip_suffix(']')).ok_or(error)?;
+            if label.is_empty() || label.contains(['[', ']']) {
+                return Err(ParseError::Drawing { line, column: start + 1 });
+            }
+            // the crate must stand above exactly one number
+            let mut below = columns.iter().enumerate().filter(|(_, &(a, b))| a <= end && start <= b);
+            match (below.next(), below.next()) {
+                (Some((j, _)), None) => s[j + 1].push(label.to_string()),
+                _ => return Err(ParseError::Drawing { line, column: start + 1 }),
+            }
+        }
+    }
+    return Ok(s);
+}
+
+// instruction in form of "move 1 from 2 to 3", `line` is used for error reporting
+#[cfg(feature = "regex")]
+pub fn parse_instruction(text: &str, line: usize) -> Result<Instruction, ParseError> {
+    use regex::Regex;
+    use std::sync::LazyLock;
+    static RE: LazyLock<Regex> = LazyLock::new(|| Regex::new(r"^move (\d+) from (\d+) to (\d+)$").unwrap());
+    // regex doesn't tell where the mismatch is, so the whole line is malformed
+    let captures = RE.captures(text).ok_or(ParseError::Instruction { line, column: 1 })?;
+    let mut parameters = [0; 3];
+    for (i, parameter) in parameters.iter_mut().enumerate() {
+        let m = captures.get(i + 1).unwrap();
+        *parameter = m.as_str().parse::<usize>()
+            .map_err(|_| ParseError::Instruction { line, column: text[..m.start()].chars().count() + 1 })?;
+    }
+    Ok(Instruction(parameters[0], parameters[1], parameters[2]))
+}
+
+// instruction in form of "move 1 from 2 to 3", `line` is used for error reporting
+#[cfg(not(feature = "regex"))]
+pub fn parse_instruction(text: &str, line: usize) -> Result<Instruction, ParseError> {
+    let chars: Vec<char> = text.chars().collect();
+    let mut pos = 0;
+    let error = |pos: usize| ParseError::Instruction { line, column: pos + 1 };
+    let keyword = |pos: &mut usize, word: &str| {
+        for expected in word.chars() {
+            if chars.get(*pos) != Some(&expected) {
+                return Err(error(*pos));
+            }
+            *pos += 1;
+        }
+        Ok(())
+    };
+    let number = |pos: &mut usize| {
+        let start = *pos;
+        while chars.get(*pos).is_some_and(|c| c.is_ascii_digit()) {
+            *pos += 1;
+        }
+        chars[start..*pos].iter().collect::<String>().parse::<usize>().map_err(|_| error(start))
+    };
+    keyword(&mut pos, "move ")?;
+    let count = number(&mut pos)?;
+    keyword(&mut pos, " from ")?;
+    let from = number(&mut pos)?;
+    keyword(&mut pos, " to ")?;
+    let to = number(&mut pos)?;
+    if pos != chars.len() {
+        return Err(error(pos));
+    }
+    Ok(Instruction(count, from, to))
+}
+
+// the drawing, an empty line and the instructions
+pub fn parse(file_content: &str) -> Result<Input, ParseError> {
+    let lines: Vec<&str> = file_content.lines().collect();
+    let dwg_len = lines.iter().position(|x| x.is_empty()).unwrap_or(lines.len());
+    let s = parse_drawing(&lines[..dwg_len], 1)?;
+    let instructions = lines.iter().enumerate().skip(dwg_len + 1)
+        .map(|(i, line)| parse_instruction(line, i + 1))
+        .collect::<Result<Vec<Instruction>, ParseError>>()?;
+    Ok((s, instructions))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::parser::*;
+
+    fn crates(labels: &[&str]) -> Vec<String> {
+        labels.iter().map(|x| x.to_string()).collect()
+    }
+
+    #[test]
+    fn drawing() {
+        let dwg = [
+            "[AB]             ",
+            "[CD] [E]   [FGH] ",
+            "  1   2      3   ",
+        ];
+        let s = parse_drawing(&dwg, 1).unwrap();
+        assert_eq!(s, vec![vec![], crates(&["CD", "AB"]), crates(&["E"]), crates(&["FGH"])]);
+        // numbers can be wider than one char
+        let row = (1..=10).map(|i| format!("[{}]", i)).collect::<Vec<_>>().join(" ");
+        let numbers = (1..=10).map(|i| format!("{:^3}", i)).collect::<Vec<_>>().join(" ");
+        let s = parse_drawing(&[&row, &numbers], 1).unwrap();
+        assert_eq!(s[10], crates(&["10"]));
+    }
+
+    #[test]
+    fn drawing_errors() {
+        let error = |line, column| Err(ParseError::Drawing { line, column });
+        assert_eq!(parse_drawing(&["[A] [B", " 1   2 "], 1), error(1, 5));
+        assert_eq!(parse_drawing(&["[A] B", " 1   2 "], 3), error(3, 5));
+        assert_eq!(parse_drawing(&["[A] []", " 1   2 "], 1), error(1, 5));
+        assert_eq!(parse_drawing(&["[A] [B]", " 1   3 "], 1), error(2, 6));
+        // crate isn't above any number or above two of them
+        assert_eq!(parse_drawing(&["[A]    [B]", " 1   2 "], 1), error(1, 8));
+        assert_eq!(parse_drawing(&["[ABCDEF]", " 1  2"], 1), error(1, 1));
+        assert_eq!(parse_drawing(&[], 1), error(1, 1));
+    }
+
+    #[test]
+    fn instructions() {
+        assert_eq!(parse_instruction("move 12 from 1 to 10", 1), Ok(Instruction(12, 1, 10)));
+        let error = |column| Err(ParseError::Instruction { line: 7, column });
+        assert_eq!(parse_instruction("move 1 from 2 to 3 ", 7), error(if cfg!(feature = "regex") { 1 } else { 19 }));
+        assert_eq!(parse_instruction("move x from 2 to 3", 7), error(if cfg!(feature = "regex") { 1 } else { 6 }));
+        assert_eq!(parse_instruction("move 1 form 2 to 3", 7), error(if cfg!(feature = "regex") { 1 } else { 9 }));
+        assert_eq!(parse_instruction("move 99999999999999999999 from 2 to 3", 7), error(6));
+        assert_eq!(parse_instruction("", 7), error(1));
+    }
+
+    #[test]
+    fn input() {
+        let text = "    [D]    \n[N] [C]    \n[Z] [M] [P]\n 1   2   3 \n\nmove 1 from 2 to 1\nmove 3 from 1 to 3\n";
+        let (s, instructions) = parse(text).unwrap();
+        assert_eq!(s[2], crates(&["M", "C", "D"]));
+        assert_eq!(instructions, vec![Instruction(1, 2, 1), Instruction(3, 1, 3)]);
+        let text = text.replace("move 3", "move3");
+        assert_eq!(parse(&text), Err(ParseError::Instruction { line: 7, column: if cfg!(feature = "regex") { 1 } else { 5 } }));
+    }
+}
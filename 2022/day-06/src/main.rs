@@ -1,32 +1,138 @@
+use std::io::{self, BufReader, Read};
+
 const INPUT_TXT: &str = include_str!("input.txt");
 
 fn main() {
     let input = read_input(INPUT_TXT);
     println!("Answer to first parts is {}", solve_first(&input, 4));
     println!("Answer to second parts is {}", solve_second(&input, 14));
+    let report = analyze(input.bytes(), &[4, 14]);
+    for (size, offsets) in &report.markers {
+        println!("{} markers of {} bytes", offsets.len(), size);
+    }
+    let (start, len) = report.longest_run;
+    println!("Longest run of distinct bytes is {} bytes from {}", len, start);
+    // prints the start-of-packet markers of the signal coming to stdin as they're found
+    if std::env::args().any(|arg| arg == "--stdin") {
+        for offset in read_markers(io::stdin().lock(), 4) {
+            println!("Marker at {}", offset.unwrap());
+        }
+    }
 }
 
-fn solve_first(input: &str, threshold: usize) -> i32 {
-    let chars = input.chars();
+#[derive(Debug, PartialEq, Eq)]
+enum Error {
+    // no window of `threshold` distinct bytes in the signal
+    NoMarker { threshold: usize },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::NoMarker { threshold } => write!(f, "no marker of {} distinct bytes", threshold),
+        }
+    }
+}
+
+// incremental marker detector, which keeps only the last `threshold` bytes
+struct MarkerDetector {
+    threshold: usize,
+    // ring buffer with the bytes of the window
+    window: Vec<u8>,
+    counts: [usize; 256],
+    // number of distinct bytes in the window
+    distinct: usize,
+    // number of bytes consumed so far
+    offset: usize,
+}
 
-    let mut counts = [0; 26];
-    let get_index = |c| c as usize - 'a' as usize;
-    for ch in input.chars().take(threshold) {
-        counts[get_index(ch)] += 1;
+impl MarkerDetector {
+    fn new(threshold: usize) -> MarkerDetector {
+        MarkerDetector { threshold, window: vec![0; threshold], counts: [0; 256], distinct: 0, offset: 0 }
     }
 
-    let zipper = chars.clone().into_iter().zip(chars.skip(threshold).into_iter());
-    for (i, (first, second)) in zipper.enumerate() {
-        if counts.iter().filter(|&&x| x > 0).count() == threshold {
-            return (i + threshold) as i32;
+    // consumes the next byte, returns the number of bytes processed so far if the
+    // last `threshold` bytes are distinct; window of 0 bytes never makes a marker
+    fn push(&mut self, byte: u8) -> Option<usize> {
+        if self.threshold == 0 {
+            self.offset += 1;
+            return None;
+        }
+        let slot = self.offset % self.threshold;
+        // drop the byte which leaves the window
+        if self.offset >= self.threshold {
+            let old = self.window[slot] as usize;
+            self.counts[old] -= 1;
+            if self.counts[old] == 0 {
+                self.distinct -= 1;
+            }
+        }
+        self.window[slot] = byte;
+        self.counts[byte as usize] += 1;
+        if self.counts[byte as usize] == 1 {
+            self.distinct += 1;
         }
-        counts[get_index(first)] -= 1;
-        counts[get_index(second)] += 1;
+        self.offset += 1;
+        if self.distinct == self.threshold { Some(self.offset) } else { None }
     }
-    if counts.iter().filter(|&&x| x > 0).count() == threshold {
-        return input.len() as i32;
+}
+
+// offsets of all markers in the byte stream, found as the bytes are consumed
+fn markers(bytes: impl IntoIterator<Item = u8>, threshold: usize) -> impl Iterator<Item = usize> {
+    let mut detector = MarkerDetector::new(threshold);
+    bytes.into_iter().filter_map(move |byte| detector.push(byte))
+}
+
+// offsets of all markers in the data read from the source, reading stops at the first error
+fn read_markers(reader: impl Read, threshold: usize) -> impl Iterator<Item = io::Result<usize>> {
+    let mut detector = MarkerDetector::new(threshold);
+    BufReader::new(reader).bytes().filter_map(move |byte| match byte {
+        Ok(byte) => detector.push(byte).map(Ok),
+        Err(e) => Some(Err(e)),
+    })
+}
+
+// markers and runs of distinct bytes found in a signal
+#[derive(Debug, PartialEq, Eq)]
+struct MarkerReport {
+    // window size and offsets of all its markers, in the order of requested sizes
+    markers: Vec<(usize, Vec<usize>)>,
+    // start and length of the longest run of distinct bytes, the earliest one wins a tie
+    longest_run: (usize, usize),
+}
+
+// finds markers of all window sizes and the longest run of distinct bytes in one pass
+fn analyze(bytes: impl IntoIterator<Item = u8>, sizes: &[usize]) -> MarkerReport {
+    let mut detectors: Vec<MarkerDetector> = sizes.iter().map(|&size| MarkerDetector::new(size)).collect();
+    let mut markers: Vec<(usize, Vec<usize>)> = sizes.iter().map(|&size| (size, vec![])).collect();
+    // offset right after the previous occurrence of every byte
+    let mut seen_at = [0usize; 256];
+    // start of the run of distinct bytes ending at the current byte
+    let mut run_start = 0;
+    let mut longest_run = (0, 0);
+    for (i, byte) in bytes.into_iter().enumerate() {
+        for (detector, (_, offsets)) in detectors.iter_mut().zip(markers.iter_mut()) {
+            if let Some(offset) = detector.push(byte) {
+                offsets.push(offset);
+            }
+        }
+        run_start = run_start.max(seen_at[byte as usize]);
+        seen_at[byte as usize] = i + 1;
+        if i + 1 - run_start > longest_run.1 {
+            longest_run = (run_start, i + 1 - run_start);
+        }
     }
-    unreachable!();
+    MarkerReport { markers, longest_run }
+}
+
+// returns the number of bytes processed when the last `threshold` bytes become distinct,
+// any byte value is a valid symbol
+fn find_marker(input: &[u8], threshold: usize) -> Result<usize, Error> {
+    markers(input.iter().copied(), threshold).next().ok_or(Error::NoMarker { threshold })
+}
+
+fn solve_first(input: &str, threshold: usize) -> i32 {
+    find_marker(input.as_bytes(), threshold).unwrap() as i32
 }
 
 fn solve_second(input: &str, threshold: usize) -> i32 {
@@ -66,5 +172,59 @@ mod tests {
             assert_eq!(solve_second(&input, 14), want[i]);
         }
     }
+
+    #[test]
+    fn any_bytes() {
+        assert_eq!(find_marker(b"AAbB", 3), Ok(4));
+        assert_eq!(find_marker(b"1122\xff\x00", 3), Ok(6));
+        assert_eq!(find_marker("żżółć".as_bytes(), 4), Ok(6));
+        assert_eq!(find_marker(b"abc", 3), Ok(3));
+    }
+
+    #[test]
+    fn streaming() {
+        let signal = TEST_INPUT_TXT[0];
+        let offsets: Vec<usize> = markers(signal.bytes(), 4).collect();
+        assert_eq!(offsets[0], 7);
+        // every marker is a window of distinct bytes
+        for &offset in &offsets {
+            let mut window = signal.as_bytes()[offset - 4..offset].to_vec();
+            window.sort();
+            window.dedup();
+            assert_eq!(window.len(), 4);
+        }
+        let from_reader: Vec<usize> = read_markers(signal.as_bytes(), 4).map(|x| x.unwrap()).collect();
+        assert_eq!(from_reader, offsets);
+        // the stream doesn't have to end
+        let mut endless = markers((0..=255u8).cycle(), 14);
+        assert_eq!(endless.next(), Some(14));
+        assert_eq!(endless.nth(10_000), Some(10_015));
+        assert_eq!(markers(b"aaaa".iter().copied(), 0).count(), 0);
+    }
+
+    #[test]
+    fn report() {
+        for (i, &line) in TEST_INPUT_TXT.iter().enumerate() {
+            let report = analyze(line.bytes(), &[4, 14, 0]);
+            assert_eq!(report.markers[0].1[0] as i32, solve_first(line, 4));
+            assert_eq!(report.markers[1].1[0] as i32, solve_second(line, 14));
+            assert_eq!(report.markers[0].1, markers(line.bytes(), 4).collect::<Vec<_>>(), "signal {}", i);
+            assert!(report.markers[2].1.is_empty());
+            // longest run has at least as many distinct bytes as the markers
+            assert!(report.longest_run.1 >= 14);
+        }
+        let report = analyze(b"abcabcdeaXYZ".iter().copied(), &[3, 5]);
+        assert_eq!(report.markers, vec![(3, vec![3, 4, 5, 6, 7, 8, 9, 10, 11, 12]), (5, vec![8, 9, 10, 11, 12])]);
+        assert_eq!(report.longest_run, (4, 8));
+        let report = analyze(b"".iter().copied(), &[1]);
+        assert_eq!(report, MarkerReport { markers: vec![(1, vec![])], longest_run: (0, 0) });
+    }
+
+    #[test]
+    fn no_marker() {
+        assert_eq!(find_marker(b"abcabc", 4), Err(Error::NoMarker { threshold: 4 }));
+        assert_eq!(find_marker(b"ab", 3), Err(Error::NoMarker { threshold: 3 }));
+        assert_eq!(find_marker(b"", 1), Err(Error::NoMarker { threshold: 1 }));
+    }
 }
 
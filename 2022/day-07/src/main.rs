@@ -1,3 +1,4 @@
+use std::collections::HashMap;
 use std::vec;
 
 const INPUT_TXT: &str = include_str!("input.txt");
@@ -6,155 +7,495 @@ fn main() {
     let input = read_input(INPUT_TXT);
     println!("Answer to first parts is {}", solve_first(&input));
     println!("Answer to second parts is {}", solve_second(&input));
+    // --pwd prints where the session ends, --list prints all the paths level by level,
+    // --tree [DEPTH], --du [DEPTH] and --json print reports, -h makes sizes human-readable;
+    // --plan prints the directories to delete, which may be limited by --max-deletions N
+    // and --protect PATH (repeatable)
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let human = args.iter().any(|x| x == "-h");
+    let depth = |i: usize| args.get(i + 1).and_then(|x| x.parse::<usize>().ok());
+    let fs = parse_all(&input);
+    let mut planner = DeletionPlanner::new(70000000, 30000000);
+    for (i, arg) in args.iter().enumerate() {
+        match (arg.as_str(), args.get(i + 1)) {
+            ("--protect", Some(path)) => planner = planner.protect(path),
+            ("--protect", None) => {
+                println!("Usage: --protect PATH");
+                return;
+            },
+            ("--max-deletions", _) => match depth(i) {
+                Some(n) => planner = planner.max_deletions(n),
+                None => {
+                    println!("Usage: --max-deletions N");
+                    return;
+                },
+            },
+            _ => {},
+        }
+    }
+    for (i, arg) in args.iter().enumerate() {
+        match arg.as_str() {
+            "--plan" => match planner.plan(&fs) {
+                Some(plan) => {
+                    println!("Deleting {} frees {}", plan.paths.join(" "), format_size(plan.freed, human));
+                },
+                None => println!("Not enough space can be freed"),
+            },
+            "--pwd" => println!("{}", fs.pwd()),
+            "--list" => fs.bfs(FileSystem::ROOT).for_each(|id| println!("{}", fs.path(id))),
+            "--tree" => print!("{}", tree(&fs, FileSystem::ROOT, depth(i), human)),
+            "--du" => print!("{}", du(&fs, depth(i), human)),
+            "--json" => println!("{}", to_json(&fs, FileSystem::ROOT)),
+            _ => {},
+        }
+    }
 }
 
+// index of the node in the file system arena
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+struct NodeId(usize);
+
 #[derive(Debug, Clone)]
 struct FSNode<'node> {
 // FileSystemNode
     name: &'node str,
+    // size of the file or cached total size of the directory
     size: usize,
-    children: Option<Vec<FSNode<'node>>>,
+    parent: Option<NodeId>,
+    // position among the children of the parent
+    index: usize,
+    children: Option<Entries<'node>>,
+}
+
+// entries of a directory in the order they were added and by name
+#[derive(Debug, Clone, Default)]
+struct Entries<'node> {
+    ids: Vec<NodeId>,
+    by_name: HashMap<&'node str, NodeId>,
 }
 
 impl<'node> FSNode<'node> {
     fn new_file(name: &'node str, size: usize) -> Self {
-        FSNode { name, size, children: None }
+        FSNode { name, size, parent: None, index: 0, children: None }
     }
 
-    fn new_directory(name: &'node str, size: usize, children: Vec<FSNode<'node>>) -> Self {
-        FSNode { name, size, children: Some(children) }
+    fn new_directory(name: &'node str) -> Self {
+        FSNode { name, size: 0, parent: None, index: 0, children: Some(Entries::default()) }
     }
 
     fn size(&self) -> usize {
         self.size
     }
 
-    fn add_size(&mut self, d: usize) {
-        self.size += d;
-    }
-
     fn name(&self) -> &'node str {
         self.name
     }
 
+    fn parent(&self) -> Option<NodeId> {
+        self.parent
+    }
+
     fn is_directory(&self) -> bool {
         self.children.is_some()
     }
 
-    fn fd(&mut self, dir_name: &'node str) -> Option<&mut FSNode<'node>> {
-        self.children.as_mut().and_then(|children| {
-                children.into_iter()
-                    .filter(|node| node.children.is_some())
-                    .find(|node| node.name() == dir_name)
-        })
+    // children of the directory, files have none
+    fn children(&self) -> &[NodeId] {
+        self.children.as_ref().map_or(&[], |entries| &entries.ids)
     }
+}
 
-    fn add_node(&mut self, node: FSNode<'node>) {
-        match &mut self.children {
-            None => unreachable!(),
-            Some(children) => {
-                assert!(!children.into_iter().any(|x| x.name() == node.name()));
-                children.push(node);
-            },
+#[derive(Debug, PartialEq, Eq)]
+enum Error {
+    // directory doesn't exist, lines are counted from 1
+    UnknownDirectory { line: usize, path: String },
+    // listed entry doesn't match the one with the same name listed before
+    ConflictingEntry { line: usize, name: String },
+    // file or directory doesn't exist
+    NoSuchEntry { line: usize, path: String },
+    AlreadyExists { line: usize, path: String },
+    // root, the current directory or its ancestors can't be removed,
+    // directories can't be moved inside themselves
+    InvalidTarget { line: usize, path: String },
+    UnknownCommand { line: usize, command: String },
+    MalformedLine { line: usize },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::UnknownDirectory { line, path } => write!(f, "line {}: no directory {}", line, path),
+            Error::ConflictingEntry { line, name } => write!(f, "line {}: {} was listed differently before", line, name),
+            Error::NoSuchEntry { line, path } => write!(f, "line {}: no such file or directory {}", line, path),
+            Error::AlreadyExists { line, path } => write!(f, "line {}: {} already exists", line, path),
+            Error::InvalidTarget { line, path } => write!(f, "line {}: can't use {} here", line, path),
+            Error::UnknownCommand { line, command } => write!(f, "line {}: unknown command {}", line, command),
+            Error::MalformedLine { line } => write!(f, "line {}: malformed line", line),
         }
     }
+}
 
-    fn iter(&self) -> FSIter {
-        FSIter::new(self)
-    }
+// file system reconstructed from the terminal session, all the nodes live in one arena
+// and refer to each other by ids
+struct FileSystem<'str> {
+    nodes: Vec<FSNode<'str>>,
+    cwd: NodeId,
 }
 
-enum FSIterState<'node> {
-    Start,
-    CurrentNode,
-    Iter(Box<dyn Iterator<Item = &'node FSNode<'node>> + 'node>),
+impl<'str> FileSystem<'str> {
+    const ROOT: NodeId = NodeId(0);
+
+    fn new() -> Self {
+        FileSystem { nodes: vec![FSNode::new_directory("/")], cwd: Self::ROOT }
+    }
+
+    fn node(&self, id: NodeId) -> &FSNode<'str> {
+        &self.nodes[id.0]
+    }
+
+    fn root(&self) -> &FSNode<'str> {
+        self.node(Self::ROOT)
+    }
+
+    fn child(&self, dir: NodeId, name: &str) -> Option<NodeId> {
+        self.node(dir).children.as_ref()?.by_name.get(name).copied()
+    }
+
+    // resolves absolute or relative (to the current directory) path into the node,
+    // None if it or any directory on the way doesn't exist
+    fn lookup(&self, path: &str) -> Option<NodeId> {
+        let mut res = if path.starts_with('/') { Self::ROOT } else { self.cwd };
+        for name in path.split('/') {
+            match name {
+                "" | "." => {},
+                // parent of the root is the root
+                ".." => res = self.node(res).parent().unwrap_or(Self::ROOT),
+                _ => res = self.child(res, name)?,
+            }
+        }
+        Some(res)
+    }
+
+    // absolute path of the node
+    fn path(&self, id: NodeId) -> String {
+        let mut names = vec![];
+        let mut current = id;
+        while let Some(parent) = self.node(current).parent() {
+            names.push(self.node(current).name());
+            current = parent;
+        }
+        names.reverse();
+        format!("/{}", names.join("/"))
+    }
+
+    // number of directories from the root to the node
+    fn depth(&self, id: NodeId) -> usize {
+        std::iter::successors(self.node(id).parent(), |&id| self.node(id).parent()).count()
+    }
+
+    // absolute path of the current directory
+    fn pwd(&self) -> String {
+        self.path(self.cwd)
+    }
+
+    fn cd(&mut self, path: &str) -> bool {
+        match self.lookup(path) {
+            Some(id) if self.node(id).is_directory() => { self.cwd = id; true },
+            _ => false,
+        }
+    }
+
+    // adds the node to the current directory, entries which are already there are skipped;
+    // returns false if the entry conflicts with the existing one
+    fn add(&mut self, node: FSNode<'str>) -> bool {
+        if let Some(existing) = self.child(self.cwd, node.name()) {
+            let existing = self.node(existing);
+            return existing.is_directory() == node.is_directory()
+                && (existing.is_directory() || existing.size() == node.size());
+        }
+        self.create(self.cwd, node);
+        true
+    }
+
+    // directory for the last name in the path and the name itself,
+    // None if the directory doesn't exist or the name can't be used for a new node
+    fn lookup_parent<'path>(&self, path: &'path str) -> Option<(NodeId, &'path str)> {
+        let path = path.strip_suffix('/').unwrap_or(path);
+        let (dir, name) = match path.rsplit_once('/') {
+            Some(("", name)) => (Self::ROOT, name),
+            Some((dir, name)) => (self.lookup(dir)?, name),
+            None => (self.cwd, path),
+        };
+        if !self.node(dir).is_directory() || matches!(name, "" | "." | "..") {
+            return None;
+        }
+        Some((dir, name))
+    }
+
+    // true if the node is the ancestor or the same node as the other one
+    fn contains(&self, ancestor: NodeId, id: NodeId) -> bool {
+        std::iter::successors(Some(id), |&id| self.node(id).parent()).any(|id| id == ancestor)
+    }
+
+    // replaces `old` part of the sizes of the directory and all the directories above it by `new`
+    fn resize(&mut self, dir: Option<NodeId>, old: usize, new: usize) {
+        let mut current = dir;
+        while let Some(dir) = current {
+            self.nodes[dir.0].size = self.nodes[dir.0].size - old + new;
+            current = self.node(dir).parent();
+        }
+    }
+
+    fn create(&mut self, dir: NodeId, node: FSNode<'str>) -> NodeId {
+        let id = NodeId(self.nodes.len());
+        self.nodes.push(node);
+        self.attach(id, dir);
+        id
+    }
+
+    fn attach(&mut self, id: NodeId, dir: NodeId) {
+        let name = self.node(id).name();
+        let entries = self.nodes[dir.0].children.as_mut().unwrap();
+        let index = entries.ids.len();
+        entries.ids.push(id);
+        entries.by_name.insert(name, id);
+        self.nodes[id.0].parent = Some(dir);
+        self.nodes[id.0].index = index;
+        self.resize(Some(dir), 0, self.node(id).size());
+    }
+
+    // takes the node out of the tree, it stays in the arena unreachable;
+    // the following siblings shift, so it takes time linear in their number
+    fn detach(&mut self, id: NodeId) {
+        let Some(dir) = self.node(id).parent() else { return };
+        let (name, index) = (self.node(id).name(), self.node(id).index);
+        let entries = self.nodes[dir.0].children.as_mut().unwrap();
+        entries.ids.remove(index);
+        entries.by_name.remove(name);
+        let shifted = entries.ids[index..].to_vec();
+        for sibling in shifted {
+            self.nodes[sibling.0].index -= 1;
+        }
+        self.nodes[id.0].parent = None;
+        self.resize(Some(dir), self.node(id).size(), 0);
+    }
+
+    fn set_file_size(&mut self, id: NodeId, size: usize) {
+        let old = std::mem::replace(&mut self.nodes[id.0].size, size);
+        self.resize(self.node(id).parent(), old, size);
+    }
+
+    // moves the node to the directory under the new name
+    fn move_to(&mut self, id: NodeId, dir: NodeId, name: &'str str) {
+        self.detach(id);
+        self.nodes[id.0].name = name;
+        self.attach(id, dir);
+    }
+
+    // nodes of the subtree in depth-first pre-order, starting with the node itself
+    fn dfs(&self, id: NodeId) -> Dfs<'_, 'str> {
+        Dfs { fs: self, start: id, next: Some(id) }
+    }
+
+    // nodes of the subtree level by level, starting with the node itself
+    fn bfs(&self, id: NodeId) -> Bfs<'_, 'str> {
+        Bfs { fs: self, queue: std::collections::VecDeque::from([id]) }
+    }
 }
 
-struct FSIter<'node> {
-    node: &'node FSNode<'node>,
-    state: FSIterState<'node>,
+// walks the tree by parent links, so it doesn't allocate
+struct Dfs<'fs, 'str> {
+    fs: &'fs FileSystem<'str>,
+    start: NodeId,
+    next: Option<NodeId>,
 }
 
-impl<'node> FSIter<'node> {
-    fn new(node: &'node FSNode<'node>) -> Self {
-        FSIter { node, state: FSIterState::Start }
+impl Iterator for Dfs<'_, '_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let res = self.next?;
+        self.next = self.fs.node(res).children().first().copied();
+        // go up until there's a next sibling
+        let mut current = res;
+        while self.next.is_none() && current != self.start {
+            let node = self.fs.node(current);
+            let parent = node.parent().unwrap();
+            self.next = self.fs.node(parent).children().get(node.index + 1).copied();
+            current = parent;
+        }
+        Some(res)
     }
 }
 
-impl<'node> Iterator for FSIter<'node> {
-    type Item = &'node FSNode<'node>;
+// keeps the ids of the pending nodes only
+struct Bfs<'fs, 'str> {
+    fs: &'fs FileSystem<'str>,
+    queue: std::collections::VecDeque<NodeId>,
+}
 
-    fn next(&mut self) -> Option<Self::Item> {
-        match &mut self.state {
-            FSIterState::Start => {
-                self.state = FSIterState::CurrentNode;
-                Some(self.node)
-            }
-            FSIterState::CurrentNode => {
-                let Some(children) = &self.node.children else {
-                    return None;
-                };
-                self.state = FSIterState::Iter(Box::new(children.into_iter().map(FSNode::iter).flatten()));
-                self.next()
-            }
-            FSIterState::Iter(iter) => iter.next(),
-        }
+impl Iterator for Bfs<'_, '_> {
+    type Item = NodeId;
+
+    fn next(&mut self) -> Option<NodeId> {
+        let res = self.queue.pop_front()?;
+        self.queue.extend(self.fs.node(res).children());
+        Some(res)
     }
 }
 
-fn ls<'str>(current: &mut FSNode<'str>, input: &Vec<Token<'str>>, idx: &mut usize) {
-    if *idx == input.len() {
-        return;
+// size in bytes or, if `human`, in the largest unit it has at least one of, like `du -h`
+fn format_size(size: usize, human: bool) -> String {
+    const UNITS: [&str; 5] = ["K", "M", "G", "T", "P"];
+    if !human || size < 1024 {
+        return size.to_string();
     }
-    while *idx != input.len() {
-        match &input[*idx] {
-            Token::cd(..) | Token::ls => break,
-            Token::dir(name) => {
-                current.add_node(FSNode::new_directory(*name, 0, vec![]));
-                *idx += 1;
-            },
-            Token::file(size, name) => {
-                current.add_node(FSNode::new_file(*name, *size));
-                current.add_size(*size);
-                *idx += 1;
-            },
+    let mut value = size as f64 / 1024.0;
+    let mut unit = 0;
+    while value >= 1024.0 && unit + 1 < UNITS.len() {
+        value /= 1024.0;
+        unit += 1;
+    }
+    format!("{:.1}{}", value, UNITS[unit])
+}
+
+// `tree`-like listing of the subtree with sizes, children deeper than `max_depth`
+// below the node are left out
+fn tree(fs: &FileSystem, id: NodeId, max_depth: Option<usize>, human: bool) -> String {
+    fn walk(fs: &FileSystem, id: NodeId, prefix: &str, depth: usize, max_depth: Option<usize>, human: bool, res: &mut String) {
+        if max_depth.is_some_and(|max| depth >= max) {
+            return;
+        }
+        let children = fs.node(id).children();
+        for (i, &child) in children.iter().enumerate() {
+            let last = i + 1 == children.len();
+            let node = fs.node(child);
+            let name = if node.is_directory() { format!("{}/", node.name()) } else { node.name().to_string() };
+            res.push_str(&format!("{}{}{} ({})\n", prefix, if last { "└── " } else { "├── " }, name, format_size(node.size(), human)));
+            let prefix = format!("{}{}", prefix, if last { "    " } else { "│   " });
+            walk(fs, child, &prefix, depth + 1, max_depth, human, res);
         }
     }
+    let mut res = format!("{} ({})\n", fs.path(id), format_size(fs.node(id).size(), human));
+    walk(fs, id, "", 0, max_depth, human, &mut res);
+    res
 }
 
-fn parse_all<'str>(input: &Vec<Token<'str>>) -> FSNode<'str> {
-    let mut root = FSNode::new_directory("/", 0, vec![]);
-    let mut i = 1;
-    parse(&mut root, input, &mut i);
-    // println!("{:?}", &root);
-    return root; 
+// `du`-like table of the directories at most `max_depth` below the root,
+// the largest ones first
+fn du(fs: &FileSystem, max_depth: Option<usize>, human: bool) -> String {
+    let mut dirs: Vec<(usize, String)> = fs.dfs(FileSystem::ROOT)
+        .filter(|&id| fs.node(id).is_directory())
+        .filter(|&id| max_depth.is_none_or(|max| fs.depth(id) <= max))
+        .map(|id| (fs.node(id).size(), fs.path(id)))
+        .collect();
+    dirs.sort_by(|a, b| b.0.cmp(&a.0).then_with(|| a.1.cmp(&b.1)));
+    let width = dirs.iter().map(|(size, _)| format_size(*size, human).len()).max().unwrap_or(0);
+    dirs.iter()
+        .map(|(size, path)| format!("{:>width$}  {}\n", format_size(*size, human), path, width = width))
+        .collect()
 }
 
-fn parse<'str>(current: &mut FSNode<'str>, input: &Vec<Token<'str>>, i: &mut usize) {
-    while *i != input.len() {
-        match &input[*i] {
-            Token::ls => { *i += 1; ls(current, input, i); },
-            Token::cd(name) => {
-                if *name == ".." {
-                    *i += 1;
-                    break;
+// subtree as JSON, directories have their children listed
+fn to_json(fs: &FileSystem, id: NodeId) -> String {
+    let node = fs.node(id);
+    let mut name = String::new();
+    for c in node.name().chars() {
+        match c {
+            '"' => name.push_str("\\\""),
+            '\\' => name.push_str("\\\\"),
+            c if c.is_control() => name.push_str(&format!("\\u{:04x}", c as u32)),
+            c => name.push(c),
+        }
+    }
+    if !node.is_directory() {
+        return format!("{{\"name\":\"{}\",\"type\":\"file\",\"size\":{}}}", name, node.size());
+    }
+    let children: Vec<String> = node.children().iter().map(|&child| to_json(fs, child)).collect();
+    format!("{{\"name\":\"{}\",\"type\":\"directory\",\"size\":{},\"children\":[{}]}}", name, node.size(), children.join(","))
+}
+
+// replays the session from the root directory
+fn replay<'str>(input: &Vec<Token<'str>>) -> Result<FileSystem<'str>, Error> {
+    let mut fs = FileSystem::new();
+    for (i, token) in input.iter().enumerate() {
+        let line = i + 1;
+        let no_such_entry = |path: &str| Error::NoSuchEntry { line, path: path.to_string() };
+        let already_exists = |path: &str| Error::AlreadyExists { line, path: path.to_string() };
+        let invalid_target = |path: &str| Error::InvalidTarget { line, path: path.to_string() };
+        match *token {
+            Token::ls => {},
+            Token::cd(path) => {
+                if !fs.cd(path) {
+                    return Err(Error::UnknownDirectory { line, path: path.to_string() });
                 }
-                if let Some(new_node) = current.fd(name) {
-                    *i += 1;
-                    parse(new_node, input, i);
-                    let d = new_node.size();
-                    current.add_size(d);
+            },
+            Token::dir(name) | Token::file(_, name) => {
+                let node = match *token {
+                    Token::file(size, _) => FSNode::new_file(name, size),
+                    _ => FSNode::new_directory(name),
+                };
+                if !fs.add(node) {
+                    return Err(Error::ConflictingEntry { line, name: name.to_string() });
+                }
+            },
+            Token::rm(path) => {
+                let id = fs.lookup(path).ok_or_else(|| no_such_entry(path))?;
+                if fs.contains(id, fs.cwd) {
+                    return Err(invalid_target(path));
+                }
+                fs.detach(id);
+            },
+            Token::mkdir(path) => {
+                let (dir, name) = fs.lookup_parent(path).ok_or_else(|| no_such_entry(path))?;
+                if fs.child(dir, name).is_some() {
+                    return Err(already_exists(path));
+                }
+                fs.create(dir, FSNode::new_directory(name));
+            },
+            Token::touch(size, path) => {
+                let (dir, name) = fs.lookup_parent(path).ok_or_else(|| no_such_entry(path))?;
+                match fs.child(dir, name) {
+                    Some(id) if fs.node(id).is_directory() => return Err(already_exists(path)),
+                    Some(id) => fs.set_file_size(id, size),
+                    None => { fs.create(dir, FSNode::new_file(name, size)); },
+                }
+            },
+            Token::mv(from, to) => {
+                let id = fs.lookup(from).ok_or_else(|| no_such_entry(from))?;
+                // into the existing directory or under the new name
+                let (dir, name) = match fs.lookup(to) {
+                    Some(dir) if fs.node(dir).is_directory() => (dir, fs.node(id).name()),
+                    _ => fs.lookup_parent(to).ok_or_else(|| no_such_entry(to))?,
+                };
+                if id == FileSystem::ROOT || fs.contains(id, dir) {
+                    return Err(invalid_target(from));
+                }
+                match fs.child(dir, name) {
+                    Some(existing) if existing == id => {},
+                    // files are overwritten
+                    Some(existing) if !fs.node(existing).is_directory() && !fs.node(id).is_directory() => {
+                        fs.detach(existing);
+                        fs.move_to(id, dir, name);
+                    },
+                    Some(_) => return Err(already_exists(to)),
+                    None => fs.move_to(id, dir, name),
                 }
             },
-            Token::dir(..) | Token::file(..) => unreachable!(), // should be consumed in 'match ls'
         }
     }
+    Ok(fs)
+}
+
+fn parse_all<'str>(input: &Vec<Token<'str>>) -> FileSystem<'str> {
+    replay(input).unwrap()
 }
 
 fn solve_first(input: &Vec<Token>) -> usize {
-    let node = parse_all(&input);
+    let fs = parse_all(&input);
     let threshold = 100000;
-    node.iter()
+    fs.dfs(FileSystem::ROOT)
+        .map(|id| fs.node(id))
         .filter_map(|node|{
             if node.is_directory() && node.size() <= threshold {
                 Some(node.size())
@@ -164,11 +505,12 @@ fn solve_first(input: &Vec<Token>) -> usize {
 }
 
 fn solve_second(input: &Vec<Token>) -> usize {
-    let node = parse_all(&input);
+    let fs = parse_all(&input);
     let total_size = 70000000;
-    let total_available = total_size - node.size(); // root
+    let total_available = total_size - fs.root().size();
     let required_free_space = 30000000;
-    node.iter()
+    fs.dfs(FileSystem::ROOT)
+        .map(|id| fs.node(id))
         .filter_map(|node|{
             if node.is_directory() && node.size() + total_available >= required_free_space {
                 Some(node.size())
@@ -177,6 +519,251 @@ fn solve_second(input: &Vec<Token>) -> usize {
         .fold(required_free_space, |smallest, size| smallest.min(size))
 }
 
+// directories chosen for deletion and their total size
+#[derive(Debug, PartialEq, Eq)]
+struct Plan {
+    paths: Vec<String>,
+    freed: usize,
+}
+
+// searches for the directories to delete to get enough free space on the disk
+struct DeletionPlanner {
+    disk_size: usize,
+    required_free_space: usize,
+    // neither these nodes nor directories containing them may be deleted
+    protected: Vec<String>,
+    max_deletions: Option<usize>,
+}
+
+impl DeletionPlanner {
+    fn new(disk_size: usize, required_free_space: usize) -> Self {
+        DeletionPlanner { disk_size, required_free_space, protected: vec![], max_deletions: None }
+    }
+
+    // absolute path of the file or directory to keep, unknown paths protect nothing
+    fn protect(mut self, path: &str) -> Self {
+        self.protected.push(path.to_string());
+        self
+    }
+
+    fn max_deletions(mut self, n: usize) -> Self {
+        self.max_deletions = Some(n);
+        self
+    }
+
+    // set of non-nested directories with the smallest total size which frees enough space,
+    // None if the constraints don't allow it
+    fn plan(&self, fs: &FileSystem) -> Option<Plan> {
+        let free = self.disk_size.saturating_sub(fs.root().size());
+        let needed = self.required_free_space.saturating_sub(free);
+        if needed == 0 {
+            return Some(Plan { paths: vec![], freed: 0 });
+        }
+        // directories in depth-first order, so subtree of dirs[i] is dirs[i..end[i]]
+        let dirs: Vec<NodeId> = fs.dfs(FileSystem::ROOT).filter(|&id| fs.node(id).is_directory()).collect();
+        let index: HashMap<NodeId, usize> = dirs.iter().enumerate().map(|(i, &id)| (id, i)).collect();
+        let mut end: Vec<usize> = (1..=dirs.len()).collect();
+        for i in (1..dirs.len()).rev() {
+            let parent = index[&fs.node(dirs[i]).parent().unwrap()];
+            end[parent] = end[parent].max(end[i]);
+        }
+        let mut blocked = vec![false; dirs.len()];
+        for id in self.protected.iter().filter_map(|path| fs.lookup(path)) {
+            let mut current = Some(id);
+            while let Some(id) = current {
+                if let Some(&i) = index.get(&id) {
+                    blocked[i] = true;
+                }
+                current = fs.node(id).parent();
+            }
+        }
+        let sweep = Sweep {
+            sizes: dirs.iter().map(|&id| fs.node(id).size()).collect(),
+            end,
+            blocked,
+            needed,
+        };
+        // the best plan regardless of the limit is the answer if it fits, counting
+        // the deletions takes more memory, so it's only done when it doesn't;
+        // the counts are bytes, so a limit above 254 is taken as 254
+        let limit = self.max_deletions.unwrap_or(usize::MAX);
+        let (freed, chosen) = match sweep.any_deletions()? {
+            (freed, chosen) if chosen.len() <= limit => (freed, chosen),
+            _ => sweep.fewest_deletions(limit.min(254) as u8)?,
+        };
+        Some(Plan { paths: chosen.iter().map(|&i| fs.path(dirs[i])).collect(), freed })
+    }
+}
+
+// goes over the directories in depth-first order; every directory may be deleted
+// after its subtree, together with what could be freed before the subtree
+struct Sweep {
+    sizes: Vec<usize>,
+    end: Vec<usize>,
+    blocked: Vec<bool>,
+    needed: usize,
+}
+
+impl Sweep {
+    // the smallest total of any number of deletions and the directories to delete
+    fn any_deletions(&self) -> Option<(usize, Vec<usize>)> {
+        let needed = self.needed;
+        let mut reached = Sizes::new(needed);
+        reached.insert(0);
+        // first[freed] is the directory deleted last when that much was freed for the first time,
+        // the sizes freed before it were reached even earlier, so they are never overwritten
+        let mut first = vec![0u32; needed];
+        // the smallest total as (total, directory deleted last)
+        let mut best: Option<(usize, usize)> = None;
+        let mut open: Vec<(usize, Sizes)> = vec![];
+        for i in 0..=self.sizes.len() {
+            while let Some((dir, before)) = open.pop_if(|(dir, _)| self.end[*dir] <= i) {
+                reached.union_shifted(&before, self.sizes[dir], |freed| first[freed] = dir as u32);
+            }
+            if i == self.sizes.len() || self.blocked[i] {
+                continue;
+            }
+            let size = self.sizes[i];
+            if let Some(freed) = reached.first_from(needed.saturating_sub(size)) {
+                if best.is_none_or(|(total, _)| freed + size < total) {
+                    best = Some((freed + size, i));
+                }
+            }
+            if size < needed {
+                open.push((i, reached.clone()));
+            }
+        }
+        let (total, last) = best?;
+        let mut chosen = vec![last];
+        let mut freed = total - self.sizes[last];
+        while freed > 0 {
+            let dir = first[freed] as usize;
+            chosen.push(dir);
+            freed -= self.sizes[dir];
+        }
+        chosen.reverse();
+        Some((total, chosen))
+    }
+
+    // the smallest total of at most `limit` deletions and the directories to delete
+    fn fewest_deletions(&self, limit: u8) -> Option<(usize, Vec<usize>)> {
+        let needed = self.needed;
+        // the fewest deletions freeing each size, only the sets which may take
+        // one more deletion are kept, so every count is below the limit
+        let mut fewest = vec![u8::MAX; needed];
+        fewest[0] = 0;
+        // the sizes whose count went down when each directory was deleted, in the order of the sweep,
+        // the last change of a size made before a directory is the way it was freed at that point
+        let mut improved: Vec<(usize, Vec<u32>)> = vec![];
+        // the smallest total as (total, directory deleted last)
+        let mut best: Option<(usize, usize)> = None;
+        let mut open: Vec<(usize, Vec<u8>)> = vec![];
+        // buffers of the snapshots already merged, reused to spare allocations
+        let mut spare: Vec<Vec<u8>> = vec![];
+        for i in 0..=self.sizes.len() {
+            while let Some((dir, before)) = open.pop_if(|(dir, _)| self.end[*dir] <= i) {
+                let size = self.sizes[dir];
+                let better = |count: u8, after: u8| count.saturating_add(1) < after.min(limit);
+                let mut sizes = vec![];
+                // most counts stay the same, checking a whole chunk without branching first is much faster
+                for (k, (after, before)) in fewest[size..].chunks_mut(64).zip(before.chunks(64)).enumerate() {
+                    if !after.iter().zip(before).fold(false, |any, (&after, &count)| any | better(count, after)) {
+                        continue;
+                    }
+                    for (j, (after, &count)) in after.iter_mut().zip(before).enumerate() {
+                        if better(count, *after) {
+                            *after = count + 1;
+                            sizes.push((size + k * 64 + j) as u32);
+                        }
+                    }
+                }
+                improved.push((dir, sizes));
+                spare.push(before);
+            }
+            if i == self.sizes.len() || self.blocked[i] || limit == 0 {
+                continue;
+            }
+            let size = self.sizes[i];
+            let from = needed.saturating_sub(size);
+            if let Some(freed) = fewest[from..].iter().position(|&count| count < limit).map(|k| from + k) {
+                if best.is_none_or(|(total, _)| freed + size < total) {
+                    best = Some((freed + size, i));
+                }
+            }
+            if size < needed {
+                let mut before = spare.pop().unwrap_or_default();
+                before.clear();
+                before.extend_from_slice(&fewest[..needed - size]);
+                open.push((i, before));
+            }
+        }
+        let (total, last) = best?;
+        let mut chosen = vec![last];
+        let mut freed = total - self.sizes[last];
+        while freed > 0 {
+            let bound = *chosen.last().unwrap();
+            let (dir, _) = improved
+                .iter()
+                .rev()
+                .find(|(dir, sizes)| self.end[*dir] <= bound && sizes.binary_search(&(freed as u32)).is_ok())?;
+            chosen.push(*dir);
+            freed -= self.sizes[*dir];
+        }
+        chosen.reverse();
+        Some((total, chosen))
+    }
+}
+
+// set of sizes below a bound
+#[derive(Debug, Clone)]
+struct Sizes {
+    bound: usize,
+    words: Vec<u64>,
+}
+
+impl Sizes {
+    fn new(bound: usize) -> Self {
+        Sizes { bound, words: vec![0; bound.div_ceil(64)] }
+    }
+
+    fn insert(&mut self, size: usize) {
+        self.words[size / 64] |= 1 << (size % 64);
+    }
+
+    // the smallest size in the set which isn't less than the given one
+    fn first_from(&self, size: usize) -> Option<usize> {
+        let mut word = size / 64;
+        let mut bits = *self.words.get(word)? & (u64::MAX << (size % 64));
+        while bits == 0 {
+            word += 1;
+            bits = *self.words.get(word)?;
+        }
+        Some(word * 64 + bits.trailing_zeros() as usize)
+    }
+
+    // adds every size of the other set increased by the given amount as long as it fits,
+    // `added` is called for the sizes which weren't in the set
+    fn union_shifted(&mut self, other: &Sizes, by: usize, mut added: impl FnMut(usize)) {
+        let (words, bits) = (by / 64, by % 64);
+        let last = self.bound.div_ceil(64);
+        for i in words..last {
+            let mut word = other.words[i - words] << bits;
+            if bits > 0 && i > words {
+                word |= other.words[i - words - 1] >> (64 - bits);
+            }
+            if i + 1 == last && !self.bound.is_multiple_of(64) {
+                word &= (1 << (self.bound % 64)) - 1;
+            }
+            let mut fresh = word & !self.words[i];
+            while fresh != 0 {
+                added(i * 64 + fresh.trailing_zeros() as usize);
+                fresh &= fresh - 1;
+            }
+            self.words[i] |= word;
+        }
+    }
+}
+
 #[derive(Debug)]
 enum Token<'str> {
     #[allow(non_camel_case_types)]
@@ -187,29 +774,57 @@ enum Token<'str> {
     dir(&'str str),
     #[allow(non_camel_case_types)]
     file(usize, &'str str),
+    // removes the file or the directory with everything inside
+    #[allow(non_camel_case_types)]
+    rm(&'str str),
+    #[allow(non_camel_case_types)]
+    mkdir(&'str str),
+    // creates the file of the given size or changes the size of the existing one
+    #[allow(non_camel_case_types)]
+    touch(usize, &'str str),
+    #[allow(non_camel_case_types)]
+    mv(&'str str, &'str str),
 }
 
 impl<'str> Token<'str> {
-    fn new(line: &'str str) -> Token {
-        match line {
-            line if line.starts_with("$ cd") => Token::cd(line.split_at(5).1),
-            line if line.starts_with("$ ls") => Token::ls,
-            line if line.starts_with("dir ") => Token::dir(line.split_at(4).1),
-            _ => {
-                let (sz, name) = line.split_once(' ').unwrap();
-                Token::file(sz.parse::<usize>().unwrap(), name)
-            }
+    // `line` is the number of the line used for error reporting
+    fn new(text: &'str str, line: usize) -> Result<Token<'str>, Error> {
+        let malformed = Error::MalformedLine { line };
+        if let Some(command) = text.strip_prefix("$ ") {
+            let words: Vec<&str> = command.split(' ').collect();
+            return match words[..] {
+                ["ls"] => Ok(Token::ls),
+                ["cd", path] => Ok(Token::cd(path)),
+                ["rm", path] => Ok(Token::rm(path)),
+                ["mkdir", path] => Ok(Token::mkdir(path)),
+                ["touch", size, path] => Ok(Token::touch(size.parse::<usize>().map_err(|_| malformed)?, path)),
+                ["mv", from, to] => Ok(Token::mv(from, to)),
+                [name, ..] if !["ls", "cd", "rm", "mkdir", "touch", "mv"].contains(&name) => {
+                    Err(Error::UnknownCommand { line, command: name.to_string() })
+                },
+                _ => Err(malformed),
+            };
+        }
+        if let Some(name) = text.strip_prefix("dir ") {
+            return Ok(Token::dir(name));
         }
+        let (sz, name) = text.split_once(' ').ok_or(Error::MalformedLine { line })?;
+        Ok(Token::file(sz.parse::<usize>().map_err(|_| malformed)?, name))
     }
 }
 
-fn read_input(file_content: &str) -> Vec<Token> {
-    file_content.lines().map(|x| Token::new(x)).collect()
+fn parse_session(file_content: &str) -> Result<Vec<Token<'_>>, Error> {
+    file_content.lines().enumerate().map(|(i, x)| Token::new(x, i + 1)).collect()
+}
+
+fn read_input(file_content: &str) -> Vec<Token<'_>> {
+    parse_session(file_content).unwrap()
 }
 
 #[cfg(test)]
 mod tests {
     use crate::*;
+    use std::collections::HashSet;
     const TEST_INPUT_TXT: &str = include_str!("input_test.txt");
 
     #[test]
@@ -223,4 +838,233 @@ mod tests {
         let input = read_input(TEST_INPUT_TXT);
         assert_eq!(solve_second(&input), 24933642);
     }
+
+    #[test]
+    fn paths() {
+        let input = read_input(TEST_INPUT_TXT);
+        let mut fs = replay(&input).unwrap();
+        assert_eq!(fs.pwd(), "/d");
+        let e = fs.lookup("/a/e").unwrap();
+        assert_eq!(fs.path(e), "/a/e");
+        assert_eq!(fs.node(e).size(), 584);
+        assert_eq!(fs.lookup("../a/./e/"), Some(e));
+        assert_eq!(fs.lookup("/../.."), Some(FileSystem::ROOT));
+        assert_eq!(fs.lookup("/a/x"), None);
+        assert_eq!(fs.path(fs.lookup("/d/d.log").unwrap()), "/d/d.log");
+        // files aren't directories
+        assert_eq!(fs.lookup("/b.txt/x"), None);
+        assert!(!fs.cd("/b.txt"));
+        assert!(fs.cd("/a/e"));
+        assert_eq!(fs.pwd(), "/a/e");
+        assert_eq!(fs.depth(fs.cwd), 2);
+        assert!(fs.cd("../../.."));
+        assert_eq!(fs.pwd(), "/");
+        assert_eq!(fs.node(fs.lookup("a").unwrap()).size(), 94853);
+    }
+
+    #[test]
+    fn traversal() {
+        let input = read_input(TEST_INPUT_TXT);
+        let fs = replay(&input).unwrap();
+        let names = |ids: Vec<NodeId>| ids.into_iter().map(|id| fs.node(id).name()).collect::<Vec<_>>();
+        assert_eq!(names(fs.dfs(FileSystem::ROOT).collect()),
+            vec!["/", "a", "e", "i", "f", "g", "h.lst", "b.txt", "c.dat", "d", "j", "d.log", "d.ext", "k"]);
+        assert_eq!(names(fs.bfs(FileSystem::ROOT).collect()),
+            vec!["/", "a", "b.txt", "c.dat", "d", "e", "f", "g", "h.lst", "j", "d.log", "d.ext", "k", "i"]);
+        // subtree only
+        let a = fs.lookup("/a").unwrap();
+        assert_eq!(names(fs.dfs(a).collect()), vec!["a", "e", "i", "f", "g", "h.lst"]);
+        assert_eq!(names(fs.bfs(a).collect()), vec!["a", "e", "f", "g", "h.lst", "i"]);
+        let i = fs.lookup("/a/e/i").unwrap();
+        assert_eq!(fs.dfs(i).collect::<Vec<_>>(), vec![i]);
+    }
+
+    #[test]
+    fn reports() {
+        let input = read_input(TEST_INPUT_TXT);
+        let fs = replay(&input).unwrap();
+        assert_eq!(format_size(1023, true), "1023");
+        assert_eq!(format_size(1536, true), "1.5K");
+        assert_eq!(format_size(48381165, true), "46.1M");
+        assert_eq!(format_size(48381165, false), "48381165");
+        assert_eq!(tree(&fs, fs.lookup("/a").unwrap(), None, false), [
+            "/a (94853)",
+            "├── e/ (584)",
+            "│   └── i (584)",
+            "├── f (29116)",
+            "├── g (2557)",
+            "└── h.lst (62596)",
+            "",
+        ].join("\n"));
+        assert_eq!(tree(&fs, FileSystem::ROOT, Some(1), true), [
+            "/ (46.1M)",
+            "├── a/ (92.6K)",
+            "├── b.txt (14.2M)",
+            "├── c.dat (8.1M)",
+            "└── d/ (23.8M)",
+            "",
+        ].join("\n"));
+        assert_eq!(tree(&fs, FileSystem::ROOT, Some(0), false), "/ (48381165)\n");
+        assert_eq!(du(&fs, None, false), "48381165  /\n24933642  /d\n   94853  /a\n     584  /a/e\n");
+        assert_eq!(du(&fs, Some(0), true), "46.1M  /\n");
+        let json = to_json(&fs, fs.lookup("/a/e").unwrap());
+        assert_eq!(json, r#"{"name":"e","type":"directory","size":584,"children":[{"name":"i","type":"file","size":584}]}"#);
+        let fs = parse_all(&read_input("$ ls\n1 \"a\\b\""));
+        assert_eq!(to_json(&fs, fs.lookup("\"a\\b\"").unwrap()), r#"{"name":"\"a\\b\"","type":"file","size":1}"#);
+    }
+
+    #[test]
+    fn deletion_plans() {
+        let input = read_input(TEST_INPUT_TXT);
+        let fs = replay(&input).unwrap();
+        let plan = |planner: DeletionPlanner| planner.plan(&fs).map(|plan| (plan.paths, plan.freed));
+        let paths = |paths: &[&str]| paths.iter().map(|x| x.to_string()).collect::<Vec<_>>();
+        // the same as the second part
+        assert_eq!(plan(DeletionPlanner::new(70000000, 30000000)), Some((paths(&["/d"]), 24933642)));
+        // protecting a file protects its directories too
+        assert_eq!(plan(DeletionPlanner::new(70000000, 30000000).protect("/d/k")), None);
+        assert_eq!(plan(DeletionPlanner::new(70000000, 30000000).protect("/a/e/i")), Some((paths(&["/d"]), 24933642)));
+        assert_eq!(plan(DeletionPlanner::new(70000000, 30000000).protect("/x")), Some((paths(&["/d"]), 24933642)));
+        // 200000 bytes are missing: no single directory fits, but /a and /a/e are nested
+        assert_eq!(plan(DeletionPlanner::new(48381165, 200000)), Some((paths(&["/d"]), 24933642)));
+        // 94000 bytes: /a is enough, /a/e is too small
+        assert_eq!(plan(DeletionPlanner::new(48381165, 94000)), Some((paths(&["/a"]), 94853)));
+        // /a/e alone isn't enough, adding it to /d only wastes space
+        assert_eq!(plan(DeletionPlanner::new(48381165, 94000).protect("/a/f")), Some((paths(&["/d"]), 24933642)));
+        assert_eq!(plan(DeletionPlanner::new(48381165, 94000).max_deletions(0)), None);
+        // enough space already
+        assert_eq!(plan(DeletionPlanner::new(70000000, 100)), Some((vec![], 0)));
+    }
+
+    #[test]
+    fn deletion_plan_combines_directories() {
+        // sizes 5, 4 and 3 in sibling directories, 7 bytes are needed: 4 + 3 beats 5 + 4
+        let session = "$ ls\ndir a\ndir b\ndir c\n$ cd a\n$ ls\n5 x\n$ cd /b\n$ ls\n4 x\n$ cd /c\n$ ls\n3 x";
+        let fs = parse_all(&read_input(session));
+        let plan = DeletionPlanner::new(12, 7).plan(&fs).unwrap();
+        assert_eq!(plan, Plan { paths: vec!["/b".to_string(), "/c".to_string()], freed: 7 });
+        let plan = DeletionPlanner::new(12, 7).protect("/c/x").plan(&fs).unwrap();
+        assert_eq!(plan, Plan { paths: vec!["/a".to_string(), "/b".to_string()], freed: 9 });
+        let plan = DeletionPlanner::new(12, 7).max_deletions(1).plan(&fs).unwrap();
+        assert_eq!(plan, Plan { paths: vec!["/".to_string()], freed: 12 });
+    }
+
+    #[test]
+    fn wide_directories() {
+        // every entry is found by name and visited in constant time
+        let n = 100000;
+        let session: String = ["$ ls".to_string()].into_iter()
+            .chain((0..n).map(|i| format!("{} f{}", i % 10, i)))
+            .chain(["$ ls".to_string(), "0 f0".to_string(), format!("{} f{}", (n - 1) % 10, n - 1)])
+            .collect::<Vec<_>>()
+            .join("\n");
+        let fs = parse_all(&read_input(&session));
+        assert_eq!(fs.root().size(), 45 * n / 10);
+        assert_eq!(fs.dfs(FileSystem::ROOT).count(), n + 1);
+        assert_eq!(fs.bfs(FileSystem::ROOT).count(), n + 1);
+        assert_eq!(fs.path(fs.lookup(&format!("f{}", n - 1)).unwrap()), format!("/f{}", n - 1));
+        // siblings stay in order after one of them is gone
+        let fs = replay(&read_input("$ ls\n1 a\n2 b\n3 c\n$ rm b\n$ touch 4 d")).unwrap();
+        let names: Vec<&str> = fs.dfs(FileSystem::ROOT).skip(1).map(|id| fs.node(id).name()).collect();
+        assert_eq!(names, vec!["a", "c", "d"]);
+        assert_eq!(fs.root().size(), 8);
+    }
+
+    #[test]
+    fn deletion_plan_many_subtrees() {
+        // 60 directories of pseudo-random sizes spread over 6 subtrees and a protected big one
+        let mut session = vec!["$ ls".to_string(), "dir big".to_string()];
+        let mut seed: usize = 12345;
+        let mut sizes = vec![];
+        for group in 0..6 {
+            session.push(format!("dir g{}", group));
+        }
+        session.push("$ cd big\n$ ls\n9000000 huge".to_string());
+        for group in 0..6 {
+            session.push(format!("$ cd /g{}\n$ ls", group));
+            for k in 0..10 {
+                session.push(format!("dir d{}", k));
+            }
+            for k in 0..10 {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let size = 1000 + (seed >> 33) % 100000;
+                sizes.push(size);
+                session.push(format!("$ cd /g{}/d{}\n$ ls\n{} f", group, k, size));
+            }
+        }
+        let session = session.join("\n");
+        let fs = parse_all(&read_input(&session));
+        let total: usize = sizes.iter().sum();
+        let disk = fs.root().size();
+        let plan = DeletionPlanner::new(disk, total / 2).protect("/big").plan(&fs).unwrap();
+        // some subset hits the target exactly
+        assert_eq!(plan.freed, total / 2);
+        assert!(plan.paths.iter().all(|path| path.starts_with("/g")));
+        let freed: usize = plan.paths.iter().map(|path| fs.node(fs.lookup(path).unwrap()).size()).sum();
+        assert_eq!(freed, plan.freed);
+        let groups: HashSet<&str> = plan.paths.iter().map(|path| &path[..3]).collect();
+        assert!(groups.len() > 1);
+        // no single directory is enough
+        assert_eq!(DeletionPlanner::new(disk, total / 2).protect("/big").max_deletions(1).plan(&fs), None);
+    }
+
+    #[test]
+    fn sessions() {
+        // session may start anywhere, visit directories with absolute paths and list them again
+        let session = "$ ls\ndir a\n5 f\n$ cd a/\n$ ls\n10 g\n$ cd /a\n$ ls\n10 g\n$ cd ..\n$ cd ..\n$ ls\ndir a\n5 f";
+        let fs = parse_all(&read_input(session));
+        assert_eq!(fs.root().size(), 15);
+        assert_eq!(fs.dfs(FileSystem::ROOT).count(), 4);
+        let error = replay(&read_input("$ cd /\n$ ls\ndir a\n$ cd b")).err();
+        assert_eq!(error, Some(Error::UnknownDirectory { line: 4, path: "b".to_string() }));
+        let error = replay(&read_input("$ ls\ndir a\n$ ls\n1 a")).err();
+        assert_eq!(error, Some(Error::ConflictingEntry { line: 4, name: "a".to_string() }));
+        let error = replay(&read_input("$ ls\n2 a\n$ ls\n1 a")).err();
+        assert_eq!(error, Some(Error::ConflictingEntry { line: 4, name: "a".to_string() }));
+    }
+
+    #[test]
+    fn commands() {
+        let mut input = read_input(TEST_INPUT_TXT);
+        let cleanup = "$ rm /a/e\n$ cd /d\n$ rm d.log\n$ mkdir /a/logs\n$ mv d.ext ../a/logs\n$ touch 10 /a/logs/new.log\n$ touch 1 j\n$ mv /a/g /a/logs/g.old\n$ mv /b.txt /c.dat";
+        input.extend(read_input(cleanup));
+        let fs = replay(&input).unwrap();
+        assert_eq!(fs.pwd(), "/d");
+        let size = |path: &str| fs.node(fs.lookup(path).unwrap()).size();
+        assert_eq!(size("/a/logs"), 5626152 + 10 + 2557);
+        assert_eq!(size("/a"), 29116 + 62596 + size("/a/logs"));
+        assert_eq!(size("/d"), 1 + 7214296);
+        assert_eq!(size("/c.dat"), 14848514);
+        assert_eq!(fs.root().size(), size("/a") + size("/d") + 14848514);
+        assert_eq!(fs.lookup("/a/e"), None);
+        assert_eq!(fs.lookup("/b.txt"), None);
+        // sizes are the same as if they were computed from scratch
+        for id in fs.dfs(FileSystem::ROOT).filter(|&id| fs.node(id).is_directory()) {
+            let files = fs.dfs(id).filter(|&id| !fs.node(id).is_directory()).map(|id| fs.node(id).size()).sum::<usize>();
+            assert_eq!(fs.node(id).size(), files);
+        }
+        assert_eq!(fs.dfs(FileSystem::ROOT).count(), 12);
+    }
+
+    #[test]
+    fn command_errors() {
+        let replay_text = |text: &str| parse_session(text).and_then(|input| replay(&input)).err();
+        let path = |x: &str| x.to_string();
+        assert_eq!(replay_text("$ ls\n$ cat a"), Some(Error::UnknownCommand { line: 2, command: path("cat") }));
+        assert_eq!(replay_text("$ ls\nabc x"), Some(Error::MalformedLine { line: 2 }));
+        assert_eq!(replay_text("$ touch x a"), Some(Error::MalformedLine { line: 1 }));
+        assert_eq!(replay_text("$ mv a"), Some(Error::MalformedLine { line: 1 }));
+        assert_eq!(replay_text("$ rm a"), Some(Error::NoSuchEntry { line: 1, path: path("a") }));
+        assert_eq!(replay_text("$ mkdir a/b"), Some(Error::NoSuchEntry { line: 1, path: path("a/b") }));
+        assert_eq!(replay_text("$ mkdir a\n$ mkdir a"), Some(Error::AlreadyExists { line: 2, path: path("a") }));
+        assert_eq!(replay_text("$ mkdir a\n$ touch 1 a"), Some(Error::AlreadyExists { line: 2, path: path("a") }));
+        assert_eq!(replay_text("$ mkdir a\n$ cd a\n$ rm /a"), Some(Error::InvalidTarget { line: 3, path: path("/a") }));
+        assert_eq!(replay_text("$ rm /"), Some(Error::InvalidTarget { line: 1, path: path("/") }));
+        assert_eq!(replay_text("$ mkdir a\n$ mkdir a/b\n$ mv a a/b"), Some(Error::InvalidTarget { line: 3, path: path("a") }));
+        assert_eq!(replay_text("$ mkdir a\n$ touch 1 b\n$ mv a b"), Some(Error::AlreadyExists { line: 3, path: path("b") }));
+        // moving the current directory keeps it current
+        let fs = replay(&read_input("$ mkdir a\n$ mkdir b\n$ cd a\n$ touch 3 f\n$ mv /a /b/c")).unwrap();
+        assert_eq!(fs.pwd(), "/b/c");
+        assert_eq!(fs.node(fs.lookup("/b").unwrap()).size(), 3);
+    }
 }
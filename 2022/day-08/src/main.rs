@@ -1,39 +1,152 @@
+use std::collections::HashSet;
+
 const INPUT_TXT: &str = include_str!("input.txt");
 
 fn main() {
     let input = read_input(INPUT_TXT);
     println!("Answer to first parts is {}", solve_first(&input));
     println!("Answer to second parts is {}", solve_second(&input));
+    // --export DIR writes the height, visibility and scenic score maps as images and CSV
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let option = |name: &str| args.iter().position(|arg| arg == name).map(|i| &args[i + 1..]);
+    if let Some(rest) = option("--export") {
+        let [dir, ..] = rest else {
+            println!("Usage: --export DIR");
+            return;
+        };
+        let dir = std::path::Path::new(dir);
+        export(&input, dir).unwrap();
+        println!("Maps are written to {}", dir.display());
+    }
+    // --from ROW COL counts the trees seen from the top of the tree or from the ground outside
+    if let Some(rest) = option("--from") {
+        let [Some(row), Some(col)] = coordinates::<isize, 2>(rest) else {
+            println!("Usage: --from ROW COL");
+            return;
+        };
+        let observer = match (usize::try_from(row), usize::try_from(col)) {
+            (Ok(r), Ok(c)) if r < input.height && c < input.width => Observer::on_tree(&input, r, c),
+            _ => Observer { row, col, height: -1 },
+        };
+        println!("{} trees are visible from {} {}", input.visible_from(&observer).len(), row, col);
+    }
+    // --sees ROW COL ROW COL tells whether the second tree is seen from the top of the first one
+    if let Some(rest) = option("--sees") {
+        let [Some(r1), Some(c1), Some(r2), Some(c2)] = coordinates::<usize, 4>(rest) else {
+            println!("Usage: --sees ROW COL ROW COL");
+            return;
+        };
+        let (from, to) = ((r1, c1), (r2, c2));
+        if [from, to].iter().all(|&(r, c)| r < input.height && c < input.width) {
+            let seen = if input.can_see(from, to) { "is" } else { "isn't" };
+            println!("Tree {} {} {} seen from {} {}", to.0, to.1, seen, from.0, from.1);
+        } else {
+            println!("No such tree");
+        }
+    }
+}
+
+// first N arguments parsed as numbers, None for missing or malformed ones
+fn coordinates<T: std::str::FromStr, const N: usize>(args: &[String]) -> [Option<T>; N] {
+    std::array::from_fn(|k| args.get(k).and_then(|x| x.parse::<T>().ok()))
 }
 
 #[derive(Debug)]
 struct ForestMap {
+    // rows one after another
     forest: Vec<i8>,
-    size: usize
+    width: usize,
+    height: usize,
 }
 
 impl ForestMap {
     fn new_visibility_map(forest_map: &ForestMap) -> ForestMap {
-        let size = forest_map.size;
-        let mut vm = ForestMap{forest: vec![0; size * size], size};
-        let last_line = (size - 1) * size;
-        for i in 0..size {
-            vm.forest[i] = 1;
-            vm.forest[last_line + i] = 1;
-            vm.forest[i * size] = 1;
-            vm.forest[(i + 1) * size - 1] = 1;
+        let (width, height) = (forest_map.width, forest_map.height);
+        let mut vm = ForestMap{forest: vec![0; width * height], width, height};
+        let last_line = (height - 1) * width;
+        for j in 0..width {
+            vm.forest[j] = 1;
+            vm.forest[last_line + j] = 1;
+        }
+        for i in 0..height {
+            vm.forest[i * width] = 1;
+            vm.forest[(i + 1) * width - 1] = 1;
         }
         return vm;
     }
 
-    #[allow(dead_code)]
-    fn print(&self) {
-        for i in 0..self.size {
-            for j in 0..self.size {
-                print!("{}", self.forest[i*self.size + j]);
+    // None outside of the map
+    fn height_at(&self, row: isize, col: isize) -> Option<i8> {
+        let (row, col) = (usize::try_from(row).ok()?, usize::try_from(col).ok()?);
+        if row >= self.height || col >= self.width {
+            return None;
+        }
+        Some(self.forest[row * self.width + col])
+    }
+
+    // trees (row, column) seen by the observer along the straight and diagonal lines;
+    // a tree is hidden by any tree in between which is at least as high as both the tree
+    // and the observer, so from the ground outside it's the rule of the first part
+    fn visible_from(&self, observer: &Observer) -> HashSet<(usize, usize)> {
+        let mut res = HashSet::new();
+        // the first and the last step k for which position + step * k is in 0..size
+        let inside = |position: isize, step: isize, size: usize| {
+            let last = size as isize - 1;
+            match step {
+                0 if (0..=last).contains(&position) => (isize::MIN, isize::MAX),
+                0 => (isize::MAX, isize::MIN),
+                1 => (-position, last - position),
+                _ => (position - last, position),
+            }
+        };
+        for (dr, dc) in DIRECTIONS {
+            let (rows, cols) = (inside(observer.row, dr, self.height), inside(observer.col, dc, self.width));
+            // the highest tree passed so far
+            let mut highest: i8 = -1;
+            for k in 1.max(rows.0).max(cols.0)..=rows.1.min(cols.1) {
+                let (row, col) = (observer.row + dr * k, observer.col + dc * k);
+                let height = self.height_at(row, col).unwrap();
+                if highest < height.max(observer.height) {
+                    res.insert((row as usize, col as usize));
+                }
+                highest = highest.max(height);
             }
-            println!("")
         }
+        res
+    }
+
+    // whether the tree `to` is seen from the top of the tree `from`, both are (row, column)
+    fn can_see(&self, from: (usize, usize), to: (usize, usize)) -> bool {
+        let observer = Observer::on_tree(self, from.0, from.1);
+        let (dr, dc) = (to.0 as isize - observer.row, to.1 as isize - observer.col);
+        let steps = dr.abs().max(dc.abs());
+        if steps == 0 || (dr != 0 && dc != 0 && dr.abs() != dc.abs()) {
+            return false;
+        }
+        let (dr, dc) = (dr.signum(), dc.signum());
+        let target = self.height_at(to.0 as isize, to.1 as isize).unwrap();
+        (1..steps)
+            .map(|k| self.height_at(observer.row + dr * k, observer.col + dc * k).unwrap())
+            .all(|height| height < target.max(observer.height))
+    }
+}
+
+// straight and diagonal directions as (row, column) steps
+const DIRECTIONS: [(isize, isize); 8] = [(-1, -1), (-1, 0), (-1, 1), (0, -1), (0, 1), (1, -1), (1, 0), (1, 1)];
+
+// someone looking at the forest from the given point, possibly outside of it;
+// height is compared with the heights of the trees, so -1 is the ground
+#[derive(Debug, Clone, Copy)]
+struct Observer {
+    row: isize,
+    col: isize,
+    height: i8,
+}
+
+impl Observer {
+    // sitting on top of the tree
+    fn on_tree(map: &ForestMap, row: usize, col: usize) -> Observer {
+        Observer { row: row as isize, col: col as isize, height: map.forest[row * map.width + col] }
     }
 }
 
@@ -45,19 +158,23 @@ fn build_visibility_map(input: &ForestMap) -> ForestMap {
             vm.forest[idx] = 1;
         }
     };
-    for i in 1..(vm.size - 1) {
-        let mut top_max: i8 = input.forest[i];
-        let mut left_max: i8 = input.forest[i*input.size];
-        let mut right_max: i8 = input.forest[(i + 1)*input.size - 1];
-        let mut bottom_max: i8 = input.forest[(input.size-1)*input.size + i];
-        for j in 1..(vm.size - 1) {
-            check_max(&mut left_max, i*vm.size + j);
-            check_max(&mut right_max, (i + 1)*vm.size - 1 - j);
-            check_max(&mut top_max, j*vm.size + i);
-            check_max(&mut bottom_max, (vm.size - j)*vm.size + i);
+    let (width, height) = (input.width, input.height);
+    for i in 1..height.saturating_sub(1) {
+        let mut left_max: i8 = input.forest[i*width];
+        let mut right_max: i8 = input.forest[(i + 1)*width - 1];
+        for j in 1..(width - 1) {
+            check_max(&mut left_max, i*width + j);
+            check_max(&mut right_max, (i + 1)*width - 1 - j);
+        }
+    }
+    for j in 1..width.saturating_sub(1) {
+        let mut top_max: i8 = input.forest[j];
+        let mut bottom_max: i8 = input.forest[(height - 1)*width + j];
+        for i in 1..(height - 1) {
+            check_max(&mut top_max, i*width + j);
+            check_max(&mut bottom_max, (height - 1 - i)*width + j);
         }
     }
-    // vm.print();
     return vm;
 }
 
@@ -66,51 +183,148 @@ fn solve_first(input: &ForestMap) -> usize {
     return vm.forest.into_iter().filter(|&x| x != 0).count();
 }
 
-fn count_trees<R: Iterator>(input: &ForestMap, max: i8, range: R) -> i32 
+// multiplies the scores of the trees on the line by their viewing distances looking back
+// along it; `stack` keeps the trees which can still block the view, lower ones are dropped
+// as soon as a tree at least as high appears
+fn multiply_by_viewing_distances<R>(input: &ForestMap, line: R, scores: &mut [usize], stack: &mut Vec<(usize, i8)>)
 where R: Iterator<Item = usize> {
-    let mut counter = 0;
-    for k in range {
-        counter += 1;
-        if input.forest[k] >= max {
-            break;
+    stack.clear();
+    for (k, idx) in line.enumerate() {
+        let height = input.forest[idx];
+        while stack.last().is_some_and(|&(_, top)| top < height) {
+            stack.pop();
         }
+        // either the blocking tree or the edge
+        scores[idx] *= k - stack.last().map_or(0, |&(pos, _)| pos);
+        stack.push((k, height));
+    }
+}
+
+// scenic score of every tree in the same order as the forest, each line is passed
+// once in both directions, so it takes linear time
+fn build_scenic_map(input: &ForestMap) -> Vec<usize> {
+    let (width, height) = (input.width, input.height);
+    let mut scores = vec![1; width * height];
+    let mut stack = Vec::with_capacity(width.max(height));
+    for i in 0..height {
+        let row = (i * width)..((i + 1) * width);
+        multiply_by_viewing_distances(input, row.clone(), &mut scores, &mut stack);
+        multiply_by_viewing_distances(input, row.rev(), &mut scores, &mut stack);
+    }
+    for j in 0..width {
+        let column = (j..width * height).step_by(width);
+        multiply_by_viewing_distances(input, column.clone(), &mut scores, &mut stack);
+        multiply_by_viewing_distances(input, column.rev(), &mut scores, &mut stack);
     }
-    return counter;
+    scores
 }
 
 fn solve_second(input: &ForestMap) -> usize {
-    let vm = build_visibility_map(input);
-    let mut max_score = 0; 
-    for i in 1..(vm.size - 1) {
-        let row_start = i * vm.size;
-        let row_end = row_start + vm.size;
-        for j in 1..(vm.size - 1) {
-            let idx = row_start + j;
-            if vm.forest[idx] == 0 {
-                continue;
-            }
-            let col_start = j;
-            let col_end = vm.size*(vm.size - 1) + j;
-            let max = input.forest[idx];
-            let mut score = count_trees(&input, max, (row_start..idx).rev());
-            score *= count_trees(&input, max, (idx+1)..row_end);
-            score *= count_trees(&input, max, (idx..=col_end).step_by(vm.size).skip(1));
-            score *= count_trees(&input, max, (col_start..idx).step_by(vm.size).rev());
-            max_score = max_score.max(score);
+    build_scenic_map(input).into_iter().max().unwrap_or(0)
+}
+
+// values of the map as unsigned numbers, e.g. to export it
+fn values(map: &ForestMap) -> Vec<usize> {
+    map.forest.iter().map(|&x| x as usize).collect()
+}
+
+// plain (ASCII) grayscale image where the highest value is white
+fn to_pgm(values: &[usize], width: usize) -> String {
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    let mut res = format!("P2\n{} {}\n{}\n", width, values.len() / width, max);
+    for row in values.chunks(width) {
+        let row: Vec<String> = row.iter().map(|x| x.to_string()).collect();
+        res.push_str(&row.join(" "));
+        res.push('\n');
+    }
+    res
+}
+
+// plain (ASCII) color image going from black through red and yellow to white
+// as the value grows up to the highest one
+fn to_ppm_heatmap(values: &[usize], width: usize) -> String {
+    let max = values.iter().copied().max().unwrap_or(0).max(1);
+    let mut res = format!("P3\n{} {}\n255\n", width, values.len() / width);
+    for row in values.chunks(width) {
+        let row: Vec<String> = row.iter()
+            .map(|&x| {
+                let level = x * 765 / max;
+                let channel = |offset: usize| level.saturating_sub(offset).min(255);
+                format!("{} {} {}", channel(0), channel(255), channel(510))
+            })
+            .collect();
+        res.push_str(&row.join("  "));
+        res.push('\n');
+    }
+    res
+}
+
+fn to_csv(values: &[usize], width: usize) -> String {
+    values.chunks(width)
+        .map(|row| row.iter().map(|x| x.to_string()).collect::<Vec<_>>().join(",") + "\n")
+        .collect()
+}
+
+// writes heights, visibility and scenic scores into the directory
+fn export(input: &ForestMap, dir: &std::path::Path) -> std::io::Result<()> {
+    let width = input.width;
+    let heights = values(input);
+    let visibility = values(&build_visibility_map(input));
+    let scores = build_scenic_map(input);
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(dir.join("heights.pgm"), to_pgm(&heights, width))?;
+    std::fs::write(dir.join("heights.csv"), to_csv(&heights, width))?;
+    std::fs::write(dir.join("visibility.pgm"), to_pgm(&visibility, width))?;
+    std::fs::write(dir.join("visibility.csv"), to_csv(&visibility, width))?;
+    std::fs::write(dir.join("scenic.ppm"), to_ppm_heatmap(&scores, width))?;
+    std::fs::write(dir.join("scenic.csv"), to_csv(&scores, width))?;
+    Ok(())
+}
+
+// lines and columns are counted from 1
+#[derive(Debug, PartialEq, Eq)]
+enum Error {
+    Empty,
+    RaggedRow { line: usize, expected: usize, found: usize },
+    InvalidHeight { line: usize, column: usize },
+}
+
+impl std::fmt::Display for Error {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self {
+            Error::Empty => write!(f, "no trees"),
+            Error::RaggedRow { line, expected, found } => {
+                write!(f, "line {}: {} trees instead of {}", line, found, expected)
+            },
+            Error::InvalidHeight { line, column } => write!(f, "{}:{}: not a digit", line, column),
         }
     }
-    return max_score as usize;
 }
 
-fn read_input(file_content: &str) -> ForestMap {
-    let size = file_content.lines().count();
-    assert_eq!(size, file_content.len() / size);
-    ForestMap{
-        forest: file_content.lines().into_iter().flat_map(
-            |line| line.chars().map(|y| y as i8 - '0' as i8))
-            .collect(),
-        size,
+// rows of digits of the same length, LF or CRLF line endings
+fn parse(file_content: &str) -> Result<ForestMap, Error> {
+    let rows: Vec<&str> = file_content.trim_end_matches(['\r', '\n']).lines().collect();
+    let width = rows.first().map_or(0, |row| row.chars().count());
+    if width == 0 {
+        return Err(Error::Empty);
     }
+    let mut forest = Vec::with_capacity(width * rows.len());
+    for (i, row) in rows.iter().enumerate() {
+        let line = i + 1;
+        let found = row.chars().count();
+        if found != width {
+            return Err(Error::RaggedRow { line, expected: width, found });
+        }
+        for (j, c) in row.chars().enumerate() {
+            let height = c.to_digit(10).ok_or(Error::InvalidHeight { line, column: j + 1 })?;
+            forest.push(height as i8);
+        }
+    }
+    Ok(ForestMap{ forest, width, height: rows.len() })
+}
+
+fn read_input(file_content: &str) -> ForestMap {
+    parse(file_content).unwrap()
 }
 
 
@@ -124,7 +338,125 @@ mod tests {
         let input = read_input(TEST_INPUT_TXT);
         let want: Vec<i8> = vec![3,0,3,7,3,2,5,5,1,2,6,5,3,3,2,3,3,5,4,9,3,5,3,9,0];
         assert_eq!(input.forest, want);
-        assert_eq!(input.size, 5);
+        assert_eq!(input.width, 5);
+        assert_eq!(input.height, 5);
+    }
+
+    #[test]
+    fn scenic_map() {
+        let input = read_input(TEST_INPUT_TXT);
+        let scores = build_scenic_map(&input);
+        assert_eq!(scores, vec![
+            0, 0, 0, 0, 0,
+            0, 1, 4, 1, 0,
+            0, 6, 1, 2, 0,
+            0, 1, 8, 3, 0,
+            0, 0, 0, 0, 0,
+        ]);
+        // the best tree can't be seen from outside
+        let input = read_input("99999\n91119\n91519\n91119\n99999");
+        assert_eq!(solve_first(&input), 16);
+        assert_eq!(solve_second(&input), 16);
+    }
+
+    #[test]
+    fn exports() {
+        let input = read_input("303\n255");
+        let heights = values(&input);
+        assert_eq!(to_pgm(&heights, input.width), "P2\n3 2\n5\n3 0 3\n2 5 5\n");
+        assert_eq!(to_csv(&heights, input.width), "3,0,3\n2,5,5\n");
+        assert_eq!(to_pgm(&values(&build_visibility_map(&input)), input.width), "P2\n3 2\n1\n1 1 1\n1 1 1\n");
+        assert_eq!(to_ppm_heatmap(&[0, 1, 2, 3], 2), "P3\n2 2\n255\n0 0 0  255 0 0\n255 255 0  255 255 255\n");
+        // nothing is visible from inside, but the image still has some maximum
+        assert_eq!(to_pgm(&[0, 0], 1), "P2\n1 2\n1\n0\n0\n");
+        let dir = std::env::temp_dir().join(format!("day-08-export-{}", std::process::id()));
+        export(&read_input(TEST_INPUT_TXT), &dir).unwrap();
+        let scenic = std::fs::read_to_string(dir.join("scenic.csv")).unwrap();
+        assert_eq!(scenic.lines().nth(3), Some("0,1,8,3,0"));
+        assert!(std::fs::read_to_string(dir.join("scenic.ppm")).unwrap().starts_with("P3\n5 5\n255\n"));
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn line_of_sight() {
+        let input = read_input(TEST_INPUT_TXT);
+        // the tree of the highest scenic score
+        assert!(input.can_see((3, 2), (1, 2)));
+        assert!(!input.can_see((3, 2), (0, 2)));
+        // over the tree of the same height to the higher one
+        assert!(input.can_see((3, 2), (3, 4)));
+        // diagonals, but not other slopes
+        assert!(input.can_see((3, 2), (1, 4)));
+        assert!(!input.can_see((3, 2), (1, 0)));
+        assert!(!input.can_see((3, 2), (0, 0)));
+        assert!(!input.can_see((3, 2), (3, 2)));
+        // the tree in between has to be lower than one of them
+        assert!(input.can_see((2, 0), (0, 0)));
+        assert!(input.can_see((0, 0), (2, 0)));
+        assert!(!input.can_see((0, 1), (2, 1)));
+        let visible = input.visible_from(&Observer::on_tree(&input, 3, 2));
+        let mut visible: Vec<_> = visible.into_iter().collect();
+        visible.sort();
+        assert_eq!(visible, vec![
+            (1, 2), (1, 4), (2, 1), (2, 2), (2, 3),
+            (3, 0), (3, 1), (3, 3), (3, 4), (4, 1), (4, 2), (4, 3),
+        ]);
+    }
+
+    #[test]
+    fn observers_outside() {
+        let input = read_input(TEST_INPUT_TXT);
+        // looking from the ground at every edge along the rows and columns gives the first part
+        let mut visible = HashSet::new();
+        for k in 0..5 {
+            for (row, col) in [(-1, k), (5, k), (k, -1), (k, 5)] {
+                let seen = input.visible_from(&Observer { row, col, height: -1 });
+                visible.extend(seen.into_iter().filter(|&(r, c)| r as isize == row || c as isize == col));
+            }
+        }
+        assert_eq!(visible.len(), solve_first(&input));
+        // far away on the diagonal
+        let seen = input.visible_from(&Observer { row: -10, col: -10, height: -1 });
+        let mut seen: Vec<_> = seen.into_iter().collect();
+        seen.sort();
+        assert_eq!(seen, vec![(0, 0), (1, 1)]);
+        // far away, only the trees on the line count
+        let seen = input.visible_from(&Observer { row: -100_000_000, col: 2, height: -1 });
+        assert_eq!(seen, HashSet::from([(0, 2), (1, 2)]));
+        let seen = input.visible_from(&Observer { row: 4, col: 100_000_000, height: 9 });
+        assert_eq!(seen, HashSet::from([(4, 4), (4, 3)]));
+        // no line crosses the map
+        assert!(input.visible_from(&Observer { row: -10, col: -25, height: 9 }).is_empty());
+    }
+
+    #[test]
+    fn rectangular() {
+        // the example without the last row and column
+        let input = read_input("3037\r\n2551\r\n6533\r\n3354\r\n\r\n");
+        assert_eq!((input.width, input.height), (4, 4));
+        let input = read_input("30373\n25512\n65332\n33549\n");
+        assert_eq!((input.width, input.height), (5, 4));
+        assert_eq!(solve_first(&input), 18);
+        assert_eq!(solve_second(&input), 4);
+        let input = read_input("3033\n2552\n6532\n3359\n3530");
+        assert_eq!(solve_first(&input), 19);
+        assert_eq!(solve_second(&input), 4);
+        // all the trees are on the edges
+        let input = read_input("123");
+        assert_eq!(solve_first(&input), 3);
+        assert_eq!(solve_second(&input), 0);
+        assert_eq!(solve_first(&read_input("1\n2")), 2);
+    }
+
+    #[test]
+    fn malformed() {
+        assert_eq!(parse("").err(), Some(Error::Empty));
+        assert_eq!(parse("\n\n").err(), Some(Error::Empty));
+        assert_eq!(parse("123\n45\n678").err(), Some(Error::RaggedRow { line: 2, expected: 3, found: 2 }));
+        assert_eq!(parse("123\n4567").err(), Some(Error::RaggedRow { line: 2, expected: 3, found: 4 }));
+        assert_eq!(parse("123\n\n456").err(), Some(Error::RaggedRow { line: 2, expected: 3, found: 0 }));
+        assert_eq!(parse("123\n4x6").err(), Some(Error::InvalidHeight { line: 2, column: 2 }));
+        assert_eq!(parse("é1\n12").err(), Some(Error::InvalidHeight { line: 1, column: 1 }));
     }
 
     #[test]
@@ -6,6 +6,23 @@ fn main() {
     let input = read_input(INPUT_TXT);
     println!("Answer to first parts is {}", solve_first(&input));
     println!("Answer to second parts is {}", solve_second(&input));
+    // --knots N reports the cells visited by every knot of the rope of N knots and where its tail ends
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let option = |name: &str| args.iter().position(|arg| arg == name).map(|i| &args[i + 1..]);
+    if let Some(rest) = option("--knots") {
+        let Some(Ok(knots @ 1..)) = rest.first().map(|x| x.parse::<usize>()) else {
+            println!("Usage: --knots N, where the rope has N > 0 knots");
+            return;
+        };
+        let mut g = simulate(&input, knots);
+        for knot in 0..knots {
+            let trace = g.trace(knot);
+            let (min, max) = trace.bounding_box();
+            println!("Knot {} visited {} cells within ({}, {})..({}, {})", knot, trace.visits(), min.0, min.1, max.0, max.1);
+        }
+        let tail = g.tail();
+        println!("Tail ends at ({}, {})", tail.0, tail.1);
+    }
 }
 
 // (x, y) == (right, up)
@@ -18,20 +35,60 @@ impl Point {
     }
 }
 
+// cells visited by a knot and the box they fit into
+#[derive(Debug)]
+struct Trace {
+    visited: HashSet<Point>,
+    min: Point,
+    max: Point,
+}
+
+impl Trace {
+    fn new(start: Point) -> Trace {
+        Trace{ visited: HashSet::from([start]), min: start, max: start }
+    }
+
+    fn add(&mut self, p: Point) {
+        self.visited.insert(p);
+        self.min = Point(self.min.0.min(p.0), self.min.1.min(p.1));
+        self.max = Point(self.max.0.max(p.0), self.max.1.max(p.1));
+    }
+
+    fn visits(&self) -> usize {
+        self.visited.len()
+    }
+
+    // corners with the lowest and the highest coordinates
+    fn bounding_box(&self) -> (Point, Point) {
+        (self.min, self.max)
+    }
+}
+
+// rope of knots following each other, the first one is the head
 #[derive(Debug)]
 struct Grid {
-    trace: HashSet<Point>,
+    // one for every knot
+    traces: Vec<Trace>,
     nodes: Vec<Point>,
 }
 
 impl Grid {
     fn new(size: usize) -> Grid {
+        assert!(size > 0, "rope needs a head");
         Grid{
-            trace: HashSet::from([Point::zero()]),
+            traces: (0..size).map(|_| Trace::new(Point::zero())).collect(),
             nodes: vec![Point::zero(); size],
         }
     }
 
+    fn trace(&self, knot: usize) -> &Trace {
+        &self.traces[knot]
+    }
+
+    fn tail_trace(&self) -> &Trace {
+        self.traces.last().unwrap()
+    }
+
     fn head(&mut self) -> &mut Point {
         self.nodes.first_mut().unwrap()
     }
@@ -45,6 +102,8 @@ impl Grid {
             let d = Point(mv.0.signum(), mv.1.signum());
             self.head().0 += d.0;
             self.head().1 += d.1;
+            let head = *self.head();
+            self.traces[0].add(head);
             self.move_tail();
             mv.0 -= d.0;
             mv.1 -= d.1;
@@ -56,30 +115,32 @@ impl Grid {
             let dx = self.nodes[i-1].0 - self.nodes[i].0;
             let dy = self.nodes[i-1].1 - self.nodes[i].1;
             if dx.abs() <= 1 && dy.abs() <= 1 {
-                continue;
+                // the rest of the rope doesn't move either
+                break;
             }
             self.nodes[i].0 += dx.signum();
             self.nodes[i].1 += dy.signum();
+            let knot = self.nodes[i];
+            self.traces[i].add(knot);
         }
-        let tail = *self.tail();
-        self.trace.insert(tail);
     }
 }
 
-fn solve_first(input: &Vec<Point>) -> usize {
-    let mut g = Grid::new(2);
+// rope of the given number of knots after all the moves
+fn simulate(input: &[Point], knots: usize) -> Grid {
+    let mut g = Grid::new(knots);
     for mv in input.iter() {
         g.move_head(*mv);
     }
-    return g.trace.len();
+    g
+}
+
+fn solve_first(input: &Vec<Point>) -> usize {
+    simulate(input, 2).tail_trace().visits()
 }
 
 fn solve_second(input: &Vec<Point>) -> usize {
-    let mut g = Grid::new(10);
-    for mv in input.iter() {
-        g.move_head(*mv);
-    }
-    return g.trace.len();
+    simulate(input, 10).tail_trace().visits()
 }
 
 fn to_coord_increment(line: &str) -> Point {
@@ -130,5 +191,26 @@ mod tests {
         let input = read_input(TEST_INPUT_PART2_TXT);
         assert_eq!(solve_second(&input), 36);
     }
+
+    #[test]
+    fn traces() {
+        let input = read_input(TEST_INPUT_TXT);
+        let g = simulate(&input, 2);
+        assert_eq!(g.trace(0).visits(), 21);
+        assert_eq!(g.trace(0).bounding_box(), (Point(0, 0), Point(5, 4)));
+        assert_eq!(g.trace(1).bounding_box(), (Point(0, 0), Point(4, 4)));
+        let input = read_input(TEST_INPUT_PART2_TXT);
+        let g = simulate(&input, 10);
+        let visits: Vec<usize> = g.traces.iter().map(Trace::visits).collect();
+        assert_eq!(visits, vec![96, 88, 80, 72, 64, 56, 50, 46, 41, 36]);
+        assert_eq!(g.trace(0).bounding_box(), (Point(-11, -5), Point(14, 15)));
+        assert_eq!(g.tail_trace().bounding_box(), (Point(-11, -5), Point(10, 6)));
+        // the same knots move the same way in a longer rope
+        let longer = simulate(&input, 12);
+        assert_eq!(longer.trace(9).visits(), 36);
+        // the head alone
+        let g = simulate(&input, 1);
+        assert_eq!(g.tail_trace().visits(), 96);
+    }
 }
 